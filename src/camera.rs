@@ -0,0 +1,42 @@
+use super::Map;
+use rltk::Point;
+use specs::prelude::*;
+
+/// Size of the part of the screen the map is drawn into. The side panel takes the rest of the
+/// width and the log the rest of the height.
+pub const VIEW_WIDTH: i32 = 58;
+pub const VIEW_HEIGHT: i32 = 43;
+
+/// The map tile drawn in the top left corner of the viewport. The view follows the player but
+/// stops at the edges of the map.
+pub fn origin(ecs: &World) -> Point {
+  let map = ecs.fetch::<Map>();
+  let player_pos = ecs.fetch::<Point>();
+  Point::new(
+    (player_pos.x - VIEW_WIDTH / 2).clamp(0, (map.width - VIEW_WIDTH).max(0)),
+    (player_pos.y - VIEW_HEIGHT / 2).clamp(0, (map.height - VIEW_HEIGHT).max(0)),
+  )
+}
+
+/// Where a map tile appears on screen, if it is inside the viewport.
+pub fn to_screen(origin: Point, tile: Point) -> Option<Point> {
+  let (x, y) = (tile.x - origin.x, tile.y - origin.y);
+  if !(0..VIEW_WIDTH).contains(&x) || !(0..VIEW_HEIGHT).contains(&y) {
+    return None;
+  }
+  Some(Point::new(x, y))
+}
+
+/// The map tile under a screen position, if the position is over the map.
+pub fn to_map(ecs: &World, screen: Point) -> Option<Point> {
+  if !(0..VIEW_WIDTH).contains(&screen.x) || !(0..VIEW_HEIGHT).contains(&screen.y) {
+    return None;
+  }
+  let origin = origin(ecs);
+  let tile = Point::new(screen.x + origin.x, screen.y + origin.y);
+  let map = ecs.fetch::<Map>();
+  if tile.x >= map.width || tile.y >= map.height {
+    return None;
+  }
+  Some(tile)
+}
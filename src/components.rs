@@ -1,4 +1,4 @@
-use rltk::RGB;
+use rltk::{RGB, RGBA};
 use specs::prelude::*;
 use specs_derive::*;
 
@@ -11,17 +11,51 @@ pub struct Position {
 #[derive(Component)]
 pub struct Renderable {
   pub glyph: rltk::FontCharType,
+  /// Index into the tile sheet, drawn instead of the glyph in the graphical tileset.
+  pub sprite: Option<u16>,
   pub fg: RGB,
-  pub bg: RGB,
+  /// May be see-through, to leave the tile beneath showing.
+  pub bg: RGBA,
   pub render_order: i32,
 }
 
 #[derive(Component, Debug)]
 pub struct Player {}
 
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum CompanionActivity {
+  Idle,
+  Resting,
+  Following,
+  Tracking,
+  Investigating,
+  Chasing,
+  Returning,
+  Swimming,
+  Tired,
+}
+
+impl CompanionActivity {
+  pub fn name(&self) -> &'static str {
+    match self {
+      CompanionActivity::Idle => "Idle",
+      CompanionActivity::Resting => "Resting",
+      CompanionActivity::Following => "Following",
+      CompanionActivity::Tracking => "Tracking",
+      CompanionActivity::Investigating => "Investigating",
+      CompanionActivity::Chasing => "Chasing",
+      CompanionActivity::Returning => "Returning",
+      CompanionActivity::Swimming => "Swimming",
+      CompanionActivity::Tired => "Tired",
+    }
+  }
+}
+
 #[derive(Component, Debug)]
 pub struct Moonshot {
   pub actions: Vec<String>,
+  pub activity: CompanionActivity,
+  pub saw_player: bool,
 }
 
 #[derive(Component)]
@@ -36,6 +70,11 @@ pub struct Name {
   pub name: String,
 }
 
+#[derive(Component, Debug)]
+pub struct Description {
+  pub text: String,
+}
+
 #[derive(Component, Debug)]
 pub struct BlocksTile {}
 
@@ -46,3 +85,91 @@ pub struct BlocksVisibility {}
 pub struct Door {
   pub open: bool,
 }
+
+#[derive(Component, Debug)]
+pub struct Hearing {
+  pub acuity: i32,
+}
+
+#[derive(Component, Debug)]
+pub struct Investigate {
+  pub x: i32,
+  pub y: i32,
+}
+
+#[derive(Component, Debug)]
+pub struct LeavesScent {
+  pub strength: i32,
+}
+
+#[derive(Component, Debug)]
+pub struct Wildlife {
+  pub fear_range: i32,
+}
+
+#[derive(Component, Debug)]
+pub struct Item {}
+
+#[derive(Component, Debug, Clone)]
+pub struct InBackpack {
+  pub owner: Entity,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct WantsToPickup {
+  pub collected_by: Entity,
+  pub item: Entity,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct WantsToDrop {
+  pub item: Entity,
+}
+
+#[derive(Component, Debug)]
+pub struct Throwable {
+  pub range: i32,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct WantsToThrow {
+  pub item: Entity,
+  pub target: rltk::Point,
+}
+
+#[derive(Component, Debug)]
+pub struct Projectile {
+  pub path: Vec<rltk::Point>,
+  pub step: usize,
+  pub timer: f32,
+}
+
+/// A short-lived visual effect, removed once its time runs out.
+#[derive(Component, Debug)]
+pub struct ParticleLifetime {
+  pub lifetime_ms: f32,
+}
+
+/// Energy for swimming, spent in deep water and recovered out of it.
+#[derive(Component, Debug)]
+pub struct Stamina {
+  pub current: i32,
+  pub max: i32,
+}
+
+/// Dripping after a dip, for this many more turns out of the water.
+#[derive(Component, Debug)]
+pub struct Wet {
+  pub turns: i32,
+}
+
+/// Turns to lose after moving through heavy going, such as wading through water.
+#[derive(Component, Debug)]
+pub struct MoveDelay {
+  pub turns: i32,
+}
+
+#[derive(Component, Debug)]
+pub struct Fetching {
+  pub item: Entity,
+}
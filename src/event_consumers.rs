@@ -0,0 +1,378 @@
+use super::{
+  CompanionActivity, GameEvent, GameEvents, GameLog, Item, LogCategory, LogLine, Map, Moonshot,
+  Name, NoiseQueue, ParticleBuilder, Player, Position, TerrainAnimation, TileType,
+};
+use rltk::RGB;
+use specs::prelude::*;
+
+/// Turns movement, splashing and doors into sounds for the hearing system to spread.
+pub struct EventSoundSystem {}
+
+impl<'a> System<'a> for EventSoundSystem {
+  type SystemData = (
+    ReadExpect<'a, GameEvents>,
+    WriteExpect<'a, NoiseQueue>,
+    ReadExpect<'a, Map>,
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, Moonshot>,
+    ReadStorage<'a, Position>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (events, mut noise, map, players, moonshots, positions) = data;
+
+    for event in events.events.iter() {
+      match event {
+        GameEvent::Moved { entity, to } => {
+          let tile = map.tiles[map.xy_idx(to.x, to.y)];
+          if tile == TileType::ShallowWater {
+            noise.emit(to.x, to.y, 6, Some(*entity), "splashing");
+          } else if tile == TileType::DeepWater {
+            noise.emit(to.x, to.y, 4, Some(*entity), "something swimming");
+          } else if players.get(*entity).is_some() {
+            noise.emit(to.x, to.y, 3, Some(*entity), "footsteps");
+          } else if let Some(moonshot) = moonshots.get(*entity) {
+            if moonshot.activity == CompanionActivity::Chasing {
+              noise.emit(to.x, to.y, 3, Some(*entity), "paws thundering past");
+            } else {
+              noise.emit(to.x, to.y, 2, Some(*entity), "paws padding nearby");
+            }
+          }
+        }
+        GameEvent::DoorOpened { entity, door } => {
+          if let Some(pos) = positions.get(*door) {
+            noise.emit(pos.x, pos.y, 6, Some(*entity), "a door creak open");
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Shows a brief flourish on the map for things worth seeing happen, and sets grass swaying
+/// where something pushed through it.
+pub struct EventParticleSystem {}
+
+impl<'a> System<'a> for EventParticleSystem {
+  type SystemData = (
+    ReadExpect<'a, GameEvents>,
+    WriteExpect<'a, ParticleBuilder>,
+    WriteExpect<'a, TerrainAnimation>,
+    ReadExpect<'a, Map>,
+    ReadStorage<'a, Position>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (events, mut particles, mut animation, map, positions) = data;
+
+    for event in events.events.iter() {
+      match event {
+        GameEvent::NoticedPlayer { entity } => {
+          if let Some(pos) = positions.get(*entity) {
+            if pos.y > 0 {
+              particles.request(pos.x, pos.y - 1, RGB::named(rltk::YELLOW), '!', 600.0);
+            }
+          }
+        }
+        GameEvent::DoorOpened { door, .. } => {
+          if let Some(pos) = positions.get(*door) {
+            particles.request(pos.x, pos.y, RGB::named(rltk::BURLYWOOD), '░', 400.0);
+          }
+        }
+        GameEvent::Moved { to, .. } if map.tiles[map.xy_idx(to.x, to.y)] == TileType::TallGrass => {
+          // The grass sways where it was pushed through, and a little to either side
+          for dy in -1..=1 {
+            for dx in -1..=1 {
+              let (x, y) = (to.x + dx, to.y + dy);
+              if x >= 0 && x < map.width && y >= 0 && y < map.height {
+                let idx = map.xy_idx(x, y);
+                if map.tiles[idx] == TileType::TallGrass {
+                  animation.rustle(idx);
+                }
+              }
+            }
+          }
+        }
+        GameEvent::Moved { to, .. }
+          if map.tiles[map.xy_idx(to.x, to.y)] == TileType::ShallowWater =>
+        {
+          // Ripples spread out across the water around whoever waded in
+          for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
+            let (x, y) = (to.x + dx, to.y + dy);
+            if x < 0 || x >= map.width || y < 0 || y >= map.height {
+              continue;
+            }
+            let tile = map.tiles[map.xy_idx(x, y)];
+            if tile == TileType::ShallowWater || tile == TileType::DeepWater {
+              particles.request(x, y, RGB::named(rltk::LIGHT_CYAN), '≈', 300.0);
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Writes what happened this turn into the game log.
+pub struct EventLogSystem {}
+
+impl<'a> System<'a> for EventLogSystem {
+  type SystemData = (
+    ReadExpect<'a, GameEvents>,
+    WriteExpect<'a, GameLog>,
+    ReadExpect<'a, Map>,
+    ReadExpect<'a, Entity>,
+    ReadStorage<'a, Name>,
+    ReadStorage<'a, Item>,
+    ReadStorage<'a, Position>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (events, mut gamelog, map, player_entity, names, items, positions) = data;
+
+    let name_of = |entity: Entity| {
+      names
+        .get(entity)
+        .map_or("something".to_string(), |n| n.name.clone())
+    };
+    let seen = |entity: Entity| {
+      positions
+        .get(entity)
+        .is_some_and(|p| map.visible_tiles[map.xy_idx(p.x, p.y)])
+    };
+    let creature = RGB::named(rltk::MAGENTA);
+    let thing = RGB::named(rltk::CYAN);
+
+    for event in events.events.iter() {
+      match event {
+        GameEvent::Moved { entity, to } if *entity == *player_entity => {
+          for here in map.tile_content[map.xy_idx(to.x, to.y)].iter() {
+            if items.get(*here).is_some() {
+              gamelog.log(
+                LogLine::new(LogCategory::Flavour)
+                  .text("You see a ")
+                  .coloured(name_of(*here), thing)
+                  .text(" here."),
+              );
+            }
+          }
+        }
+        GameEvent::Bumped { entity, target } if *entity == *player_entity => {
+          gamelog.log(
+            LogLine::new(LogCategory::Companion)
+              .text("You bump into ")
+              .coloured(name_of(*target), creature),
+          );
+        }
+        GameEvent::DoorOpened { entity, .. } if *entity == *player_entity => {
+          gamelog.log(LogLine::new(LogCategory::Flavour).text("You push the door open."));
+        }
+        GameEvent::NoticedPlayer { entity } if seen(*entity) => {
+          gamelog.log(
+            LogLine::new(LogCategory::Companion)
+              .coloured(name_of(*entity), creature)
+              .text(" spots you, tail wagging."),
+          );
+        }
+        GameEvent::HeardNoise {
+          listener,
+          source,
+          description,
+        } if *listener == *player_entity && !map.visible_tiles[map.xy_idx(source.x, source.y)] => {
+          gamelog
+            .log(LogLine::new(LogCategory::Flavour).text(format!("You hear {}.", description)));
+        }
+        GameEvent::ActivityChanged { entity, activity } if seen(*entity) => {
+          let doing = match activity {
+            CompanionActivity::Resting => " stops to rest.",
+            CompanionActivity::Following => " follows you.",
+            CompanionActivity::Tracking => " follows your scent.",
+            CompanionActivity::Investigating => " goes to investigate a noise.",
+            CompanionActivity::Chasing => " chases after it.",
+            CompanionActivity::Returning => " trots back to you.",
+            CompanionActivity::Swimming => " splashes about, in no hurry to leave the water.",
+            CompanionActivity::Tired => " is tiring, and paddles for the shore.",
+            CompanionActivity::Idle => continue,
+          };
+          gamelog.log(
+            LogLine::new(LogCategory::Companion)
+              .coloured(name_of(*entity), creature)
+              .text(doing),
+          );
+        }
+        GameEvent::PickedUp { entity, item } => {
+          if *entity == *player_entity {
+            gamelog.log(
+              LogLine::new(LogCategory::System)
+                .text("You pick up the ")
+                .coloured(name_of(*item), thing)
+                .text("."),
+            );
+          } else if seen(*entity) {
+            gamelog.log(
+              LogLine::new(LogCategory::Companion)
+                .coloured(name_of(*entity), creature)
+                .text(" picks up the ")
+                .coloured(name_of(*item), thing)
+                .text("."),
+            );
+          }
+        }
+        GameEvent::Dropped { entity, item } => {
+          if *entity == *player_entity {
+            gamelog.log(
+              LogLine::new(LogCategory::System)
+                .text("You drop the ")
+                .coloured(name_of(*item), thing)
+                .text("."),
+            );
+          } else if seen(*entity) {
+            gamelog.log(
+              LogLine::new(LogCategory::Companion)
+                .coloured(name_of(*entity), creature)
+                .text(" drops the ")
+                .coloured(name_of(*item), thing)
+                .text(" beside you."),
+            );
+          }
+        }
+        GameEvent::LostInWater { entity, item } => {
+          if *entity == *player_entity {
+            gamelog.log(
+              LogLine::new(LogCategory::Warning)
+                .text("The ")
+                .coloured(name_of(*item), thing)
+                .text(" slips from your grasp as you swim, and drifts ashore."),
+            );
+          } else if seen(*entity) {
+            gamelog.log(
+              LogLine::new(LogCategory::Companion)
+                .coloured(name_of(*entity), creature)
+                .text(" lets go of the ")
+                .coloured(name_of(*item), thing)
+                .text(", and it drifts ashore."),
+            );
+          }
+        }
+        GameEvent::WeatherChanged { weather } => {
+          gamelog.log(LogLine::new(LogCategory::Flavour).text(weather.announcement()));
+        }
+        GameEvent::Thrown { entity, item } if *entity == *player_entity => {
+          gamelog.log(
+            LogLine::new(LogCategory::System)
+              .text("You throw the ")
+              .coloured(name_of(*item), thing)
+              .text("."),
+          );
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Running totals of what the player has been up to.
+#[derive(Default)]
+pub struct Statistics {
+  pub steps: i32,
+  pub bumps: i32,
+  pub doors_opened: i32,
+  pub noises_heard: i32,
+  pub items_thrown: i32,
+  pub fetches: i32,
+}
+
+pub struct StatisticsSystem {}
+
+impl<'a> System<'a> for StatisticsSystem {
+  type SystemData = (
+    ReadExpect<'a, GameEvents>,
+    WriteExpect<'a, Statistics>,
+    ReadExpect<'a, Entity>,
+    ReadStorage<'a, Moonshot>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (events, mut stats, player_entity, moonshots) = data;
+
+    for event in events.events.iter() {
+      match event {
+        GameEvent::Moved { entity, .. } if *entity == *player_entity => stats.steps += 1,
+        GameEvent::Bumped { entity, .. } if *entity == *player_entity => stats.bumps += 1,
+        GameEvent::DoorOpened { entity, .. } if *entity == *player_entity => {
+          stats.doors_opened += 1
+        }
+        GameEvent::HeardNoise { listener, .. } if *listener == *player_entity => {
+          stats.noises_heard += 1
+        }
+        GameEvent::Thrown { entity, .. } if *entity == *player_entity => stats.items_thrown += 1,
+        GameEvent::Dropped { entity, .. } if moonshots.get(*entity).is_some() => stats.fetches += 1,
+        _ => {}
+      }
+    }
+  }
+}
+
+struct Achievement {
+  name: &'static str,
+  description: &'static str,
+  earned: fn(&Statistics) -> bool,
+}
+
+const ACHIEVEMENTS: &[Achievement] = &[
+  Achievement {
+    name: "Open Sesame",
+    description: "open a door",
+    earned: |s| s.doors_opened >= 1,
+  },
+  Achievement {
+    name: "Good Dog",
+    description: "play a game of fetch",
+    earned: |s| s.fetches >= 1,
+  },
+  Achievement {
+    name: "Wanderer",
+    description: "walk 100 steps",
+    earned: |s| s.steps >= 100,
+  },
+  Achievement {
+    name: "Keen Ears",
+    description: "hear 10 noises",
+    earned: |s| s.noises_heard >= 10,
+  },
+];
+
+/// Names of the achievements unlocked so far.
+#[derive(Default)]
+pub struct Achievements {
+  pub unlocked: Vec<&'static str>,
+}
+
+pub struct AchievementSystem {}
+
+impl<'a> System<'a> for AchievementSystem {
+  type SystemData = (
+    ReadExpect<'a, Statistics>,
+    WriteExpect<'a, Achievements>,
+    WriteExpect<'a, GameLog>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (stats, mut achievements, mut gamelog) = data;
+
+    for achievement in ACHIEVEMENTS.iter() {
+      if achievements.unlocked.contains(&achievement.name) || !(achievement.earned)(&stats) {
+        continue;
+      }
+      achievements.unlocked.push(achievement.name);
+      gamelog.log(
+        LogLine::new(LogCategory::System)
+          .text("Achievement unlocked: ")
+          .coloured(achievement.name, RGB::named(rltk::GOLD))
+          .text(format!(" ({}).", achievement.description)),
+      );
+    }
+  }
+}
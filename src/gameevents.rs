@@ -0,0 +1,62 @@
+use super::{CompanionActivity, Weather};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Something that happened this turn, for consumers to turn into log lines, sounds and stats.
+pub enum GameEvent {
+  Moved {
+    entity: Entity,
+    to: Point,
+  },
+  Bumped {
+    entity: Entity,
+    target: Entity,
+  },
+  DoorOpened {
+    entity: Entity,
+    door: Entity,
+  },
+  NoticedPlayer {
+    entity: Entity,
+  },
+  HeardNoise {
+    listener: Entity,
+    source: Point,
+    description: String,
+  },
+  ActivityChanged {
+    entity: Entity,
+    activity: CompanionActivity,
+  },
+  PickedUp {
+    entity: Entity,
+    item: Entity,
+  },
+  Dropped {
+    entity: Entity,
+    item: Entity,
+  },
+  Thrown {
+    entity: Entity,
+    item: Entity,
+  },
+  /// Let go of something to swim, and it drifted ashore.
+  LostInWater {
+    entity: Entity,
+    item: Entity,
+  },
+  WeatherChanged {
+    weather: Weather,
+  },
+}
+
+#[derive(Default)]
+pub struct GameEvents {
+  pub events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+  pub fn emit(&mut self, event: GameEvent) {
+    self.events.push(event);
+  }
+}
@@ -1,9 +1,142 @@
+use rltk::RGB;
+
+/// Oldest entries are dropped once the history grows past this.
+pub const MAX_HISTORY: usize = 1000;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum LogCategory {
+  Flavour,
+  System,
+  Companion,
+  Warning,
+}
+
+impl LogCategory {
+  pub fn name(&self) -> &'static str {
+    match self {
+      LogCategory::Flavour => "Flavour",
+      LogCategory::System => "System",
+      LogCategory::Companion => "Companion",
+      LogCategory::Warning => "Warning",
+    }
+  }
+
+  /// The colour used for the plain text of a line in this category.
+  pub fn colour(&self) -> RGB {
+    match self {
+      LogCategory::Flavour => RGB::named(rltk::WHITE),
+      LogCategory::System => RGB::named(rltk::LIGHT_GRAY),
+      LogCategory::Companion => RGB::named(rltk::PLUM),
+      LogCategory::Warning => RGB::named(rltk::ORANGE),
+    }
+  }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct LogFragment {
+  pub colour: RGB,
+  pub text: String,
+}
+
+/// A log line under construction, made of differently coloured spans.
+pub struct LogLine {
+  category: LogCategory,
+  fragments: Vec<LogFragment>,
+}
+
+impl LogLine {
+  pub fn new(category: LogCategory) -> LogLine {
+    LogLine {
+      category,
+      fragments: Vec::new(),
+    }
+  }
+
+  /// Appends text in the category's colour.
+  pub fn text<S: ToString>(mut self, text: S) -> LogLine {
+    self.fragments.push(LogFragment {
+      colour: self.category.colour(),
+      text: text.to_string(),
+    });
+    self
+  }
+
+  /// Appends text in its own colour, such as a name.
+  pub fn coloured<S: ToString>(mut self, text: S, colour: RGB) -> LogLine {
+    self.fragments.push(LogFragment {
+      colour,
+      text: text.to_string(),
+    });
+    self
+  }
+}
+
+pub struct LogEntry {
+  pub category: LogCategory,
+  pub turn: i32,
+  pub fragments: Vec<LogFragment>,
+  pub count: i32,
+}
+
+impl LogEntry {
+  pub fn text(&self) -> String {
+    self.fragments.iter().map(|f| f.text.as_str()).collect()
+  }
+}
+
+#[derive(Default)]
 pub struct GameLog {
-  pub entries: Vec<String>,
+  pub entries: Vec<LogEntry>,
+  pub turn: i32,
+  /// How many warnings have been logged in total, counting stacked repeats.
+  pub warnings: usize,
 }
 
+/// The hour the game starts at; every turn is a minute after that.
+const START_HOUR: i32 = 7;
+
 impl GameLog {
-  pub fn add(mut self, msg: String) {
-    self.entries.push(msg);
+  /// The in-game clock as hour, minute and a name for that part of the day.
+  pub fn time_of_day(&self) -> (i32, i32, &'static str) {
+    let minutes = START_HOUR * 60 + self.turn;
+    let hour = (minutes / 60) % 24;
+    let period = match hour {
+      5..=6 => "Dawn",
+      7..=11 => "Morning",
+      12..=16 => "Afternoon",
+      17..=20 => "Evening",
+      _ => "Night",
+    };
+    (hour, minutes % 60, period)
+  }
+
+  /// Logs a plain flavour message.
+  pub fn add(&mut self, msg: String) {
+    self.log(LogLine::new(LogCategory::Flavour).text(msg));
+  }
+
+  /// Logs a line, stacking it onto the previous entry if it says the same thing.
+  pub fn log(&mut self, line: LogLine) {
+    if line.category == LogCategory::Warning {
+      self.warnings += 1;
+    }
+    if let Some(last) = self.entries.last_mut() {
+      if last.category == line.category && last.fragments == line.fragments {
+        last.count += 1;
+        last.turn = self.turn;
+        return;
+      }
+    }
+
+    self.entries.push(LogEntry {
+      category: line.category,
+      turn: self.turn,
+      fragments: line.fragments,
+      count: 1,
+    });
+    if self.entries.len() > MAX_HISTORY {
+      let excess = self.entries.len() - MAX_HISTORY;
+      self.entries.drain(..excess);
+    }
   }
 }
@@ -1,7 +1,11 @@
-use super::{Map, Name, Position};
-use crate::gamelog::GameLog;
+use super::{
+  camera, layers, minimap, travel, Achievements, Action, DebugOverlay, Description, Door,
+  InBackpack, KeyBindings, Map, Moonshot, Name, Player, Position, Renderable, RunState, Settings,
+  Stamina, State, Statistics, Throwable, Viewshed, Weather, Wet, ACTIONS,
+};
+use crate::gamelog::{GameLog, LogCategory, LogEntry};
 use rltk::Point;
-use rltk::{Console, Rltk, RGB};
+use rltk::{Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
 
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
@@ -16,32 +20,218 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 
   let log = ecs.fetch::<GameLog>();
 
-  let mut y = 44;
-  for s in log.entries.iter().rev() {
-    if y < 49 {
-      ctx.print(2, y, s);
+  for (y, entry) in (44..49).zip(log.entries.iter().rev()) {
+    draw_log_entry(ctx, 2, y, entry);
+  }
+
+  draw_side_panel(ecs, ctx);
+  minimap::draw_minimap(ecs, ctx);
+
+  // Preview the walk a click would start
+  if *ecs.fetch::<RunState>() == RunState::AwaitingInput && !travel::is_travelling(ecs) {
+    let origin = camera::origin(ecs);
+    let path = camera::to_map(ecs, ctx.mouse_point()).map(|t| travel::preview_path(ecs, t));
+    for step in path.unwrap_or_default().iter() {
+      if let Some(screen) = camera::to_screen(origin, *step) {
+        ctx.set_bg(
+          screen.x,
+          screen.y,
+          layers::highlight(RGB::named(rltk::DARK_CYAN)),
+        );
+      }
     }
-    y += 1;
   }
 
   let mouse_pos = ctx.mouse_pos();
-  ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::MAGENTA));
+  ctx.set_bg(
+    mouse_pos.0,
+    mouse_pos.1,
+    layers::highlight(RGB::named(rltk::MAGENTA)),
+  );
   draw_tooltips(ecs, ctx);
 }
 
-fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
+/// Prints a log entry with its turn stamp, coloured spans and repeat count.
+fn draw_log_entry(ctx: &mut Rltk, x: i32, y: i32, entry: &LogEntry) {
+  let stamp = format!("{:>4} ", entry.turn);
+  ctx.print_color(
+    x,
+    y,
+    RGB::named(rltk::DARK_GRAY),
+    RGB::named(rltk::BLACK),
+    &stamp,
+  );
+
+  let mut x = x + stamp.len() as i32;
+  for fragment in entry.fragments.iter() {
+    ctx.print_color(
+      x,
+      y,
+      fragment.colour,
+      RGB::named(rltk::BLACK),
+      &fragment.text,
+    );
+    x += fragment.text.chars().count() as i32;
+  }
+
+  if entry.count > 1 {
+    ctx.print_color(
+      x,
+      y,
+      RGB::named(rltk::YELLOW),
+      RGB::named(rltk::BLACK),
+      format!(" x{}", entry.count),
+    );
+  }
+}
+
+/// The eight-way compass direction of `to` as seen from `from`.
+fn compass(from: Point, to: Point) -> &'static str {
+  const POINTS: [&str; 8] = ["E", "SE", "S", "SW", "W", "NW", "N", "NE"];
+  if from == to {
+    return "here";
+  }
+  let angle = ((to.y - from.y) as f32).atan2((to.x - from.x) as f32);
+  let sector = (angle / std::f32::consts::FRAC_PI_4).round() as i32;
+  POINTS[sector.rem_euclid(8) as usize]
+}
+
+/// Where the player is, what time and weather it is, what Moonshot is up to and what can be seen.
+fn draw_side_panel(ecs: &World, ctx: &mut Rltk) {
   let map = ecs.fetch::<Map>();
+  let log = ecs.fetch::<GameLog>();
+  let weather = ecs.fetch::<Weather>();
+  let player_pos = *ecs.fetch::<Point>();
+  let entities = ecs.entities();
   let names = ecs.read_storage::<Name>();
   let positions = ecs.read_storage::<Position>();
+  let renderables = ecs.read_storage::<Renderable>();
+  let moonshots = ecs.read_storage::<Moonshot>();
+  let stamina = ecs.read_storage::<Stamina>();
+  let wet = ecs.read_storage::<Wet>();
+  let player_entity = *ecs.fetch::<Entity>();
 
-  let mouse_pos = ctx.mouse_pos();
-  if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
-    return;
+  let left = camera::VIEW_WIDTH;
+  let white = RGB::named(rltk::WHITE);
+  let grey = RGB::named(rltk::LIGHT_GRAY);
+  let black = RGB::named(rltk::BLACK);
+  let heading = RGB::named(rltk::YELLOW);
+  ctx.draw_box(left, 0, 79 - left, camera::VIEW_HEIGHT - 1, white, black);
+
+  let mut y = 1;
+  ctx.print_color(left + 1, y, heading, black, "You");
+  let idx = map.xy_idx(player_pos.x, player_pos.y);
+  let (hour, minute, period) = log.time_of_day();
+  let lines = [
+    format!("At     {},{}", player_pos.x, player_pos.y),
+    format!("Ground {}", map.tiles[idx].name()),
+    format!("Turn   {}", log.turn),
+    format!("Time   {:02}:{:02} {}", hour, minute, period),
+    format!("Sky    {}", weather.describe()),
+  ];
+  for line in lines.iter() {
+    y += 1;
+    ctx.print_color(left + 1, y, grey, black, line);
+  }
+  if let Some(stamina) = stamina.get(player_entity) {
+    y += 1;
+    let colour = if stamina.current == 0 {
+      RGB::named(rltk::ORANGE)
+    } else {
+      grey
+    };
+    ctx.print_color(
+      left + 1,
+      y,
+      colour,
+      black,
+      format!("Energy {}/{}", stamina.current, stamina.max),
+    );
+  }
+  let water = RGB::named(rltk::LIGHT_BLUE);
+  if wet.get(player_entity).is_some() {
+    y += 1;
+    ctx.print_color(left + 1, y, water, black, "Wet");
+  }
+
+  for (dog, moonshot, pos) in (&entities, &moonshots, &positions).join() {
+    y += 2;
+    let name = names.get(dog).map_or("Moonshot", |n| &n.name);
+    ctx.print_color(left + 1, y, heading, black, name);
+    let here = Point::new(pos.x, pos.y);
+    let distance = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, here);
+    let unseen = if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+      ""
+    } else {
+      " (unseen)"
+    };
+    y += 1;
+    ctx.print_color(
+      left + 1,
+      y,
+      grey,
+      black,
+      format!(
+        "Dist   {:.0} {}{}",
+        distance,
+        compass(player_pos, here),
+        unseen
+      ),
+    );
+    y += 1;
+    ctx.print_color(
+      left + 1,
+      y,
+      grey,
+      black,
+      format!("Doing  {}", moonshot.activity.name()),
+    );
+    if wet.get(dog).is_some() {
+      y += 1;
+      ctx.print_color(left + 1, y, water, black, "Wet");
+    }
+  }
+
+  y += 2;
+  ctx.print_color(left + 1, y, heading, black, "In view");
+  let in_view = travel::visible_entities(ecs);
+  if in_view.is_empty() {
+    y += 1;
+    ctx.print_color(left + 1, y, RGB::named(rltk::DARK_GRAY), black, "Nothing");
   }
+  for entity in in_view.iter() {
+    y += 1;
+    if y >= camera::VIEW_HEIGHT - 2 {
+      break;
+    }
+    if let Some(render) = renderables.get(*entity) {
+      ctx.set(left + 1, y, render.fg, black, render.glyph);
+    }
+    if let Some(name) = names.get(*entity) {
+      ctx.print_color(left + 3, y, white, black, &name.name);
+    }
+  }
+}
+
+fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
+  let entities = ecs.entities();
+  let names = ecs.read_storage::<Name>();
+  let positions = ecs.read_storage::<Position>();
+  let wet = ecs.read_storage::<Wet>();
+
+  let mouse_pos = ctx.mouse_pos();
+  let tile = match camera::to_map(ecs, ctx.mouse_point()) {
+    Some(tile) => tile,
+    None => return,
+  };
   let mut tooltip: Vec<String> = Vec::new();
-  for (name, position) in (&names, &positions).join() {
-    if position.x == mouse_pos.0 && position.y == mouse_pos.1 {
-      tooltip.push(name.name.to_string());
+  for (entity, name, position) in (&entities, &names, &positions).join() {
+    if position.x == tile.x && position.y == tile.y {
+      if wet.get(entity).is_some() {
+        tooltip.push(format!("{} (wet)", name.name));
+      } else {
+        tooltip.push(name.name.to_string());
+      }
     }
   }
 
@@ -54,11 +244,10 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     }
     width += 3;
 
-    if mouse_pos.0 > 40 {
+    if mouse_pos.0 > camera::VIEW_WIDTH / 2 {
       let arrow_pos = Point::new(mouse_pos.0 - 2, mouse_pos.1);
       let left_x = mouse_pos.0 - width;
-      let mut y = mouse_pos.1;
-      for s in tooltip.iter() {
+      for (y, s) in (mouse_pos.1..).zip(tooltip.iter()) {
         ctx.print_color(
           left_x,
           y,
@@ -73,23 +262,21 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
             y,
             RGB::named(rltk::WHITE),
             RGB::named(rltk::DARKGREY),
-            &" ".to_string(),
+            " ",
           );
         }
-        y += 1;
       }
       ctx.print_color(
         arrow_pos.x,
         arrow_pos.y,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::DARKGREY),
-        &"->".to_string(),
+        "->",
       );
     } else {
       let arrow_pos = Point::new(mouse_pos.0 + 1, mouse_pos.1);
       let left_x = mouse_pos.0 + 3;
-      let mut y = mouse_pos.1;
-      for s in tooltip.iter() {
+      for (y, s) in (mouse_pos.1..).zip(tooltip.iter()) {
         ctx.print_color(
           left_x + 1,
           y,
@@ -104,18 +291,895 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
             y,
             RGB::named(rltk::WHITE),
             RGB::named(rltk::DARKGREY),
-            &" ".to_string(),
+            " ",
           );
         }
-        y += 1;
       }
       ctx.print_color(
         arrow_pos.x,
         arrow_pos.y,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::DARKGREY),
-        &"<-".to_string(),
+        "<-",
       );
     }
   }
 }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ItemMenuResult {
+  Cancel,
+  NoResponse,
+  Selected,
+}
+
+/// Collects the player's backpack contents that pass `filter`, with their names.
+fn backpack_items(ecs: &World, filter: impl Fn(Entity) -> bool) -> Vec<(Entity, String)> {
+  let player_entity = ecs.fetch::<Entity>();
+  let names = ecs.read_storage::<Name>();
+  let backpack = ecs.read_storage::<InBackpack>();
+  let entities = ecs.entities();
+
+  (&entities, &backpack, &names)
+    .join()
+    .filter(|item| item.1.owner == *player_entity && filter(item.0))
+    .map(|item| (item.0, item.2.name.clone()))
+    .collect()
+}
+
+/// Lists the given items under `title` and lets the player pick an entry by letter.
+fn item_menu(
+  ctx: &mut Rltk,
+  title: &str,
+  inventory: Vec<(Entity, String)>,
+) -> (ItemMenuResult, Option<Entity>) {
+  let count = inventory.len() as i32;
+
+  let top = 25 - (count / 2);
+  ctx.draw_box(
+    15,
+    top - 2,
+    31,
+    count + 3,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+  );
+  ctx.print_color(
+    18,
+    top - 2,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    title,
+  );
+  ctx.print_color(
+    18,
+    top + count + 1,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "ESCAPE to cancel",
+  );
+
+  for (j, (_entity, name)) in inventory.iter().enumerate() {
+    let y = top + j as i32;
+    ctx.set(
+      17,
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      rltk::to_cp437('('),
+    );
+    ctx.set(
+      18,
+      y,
+      RGB::named(rltk::YELLOW),
+      RGB::named(rltk::BLACK),
+      97 + j as rltk::FontCharType,
+    );
+    ctx.set(
+      19,
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      rltk::to_cp437(')'),
+    );
+
+    ctx.print(21, y, name);
+  }
+
+  match ctx.key {
+    None => (ItemMenuResult::NoResponse, None),
+    Some(VirtualKeyCode::Escape) => (ItemMenuResult::Cancel, None),
+    Some(key) => {
+      let selection = rltk::letter_to_option(key);
+      if selection > -1 && selection < count {
+        return (
+          ItemMenuResult::Selected,
+          Some(inventory[selection as usize].0),
+        );
+      }
+      (ItemMenuResult::NoResponse, None)
+    }
+  }
+}
+
+pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+  let inventory = backpack_items(&gs.ecs, |_| true);
+  item_menu(ctx, "Inventory", inventory)
+}
+
+pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+  let inventory = backpack_items(&gs.ecs, |_| true);
+  item_menu(ctx, "Drop Which Item?", inventory)
+}
+
+pub fn throw_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+  let throwables = gs.ecs.read_storage::<Throwable>();
+  let inventory = backpack_items(&gs.ecs, |item| throwables.get(item).is_some());
+  item_menu(ctx, "Throw Which Item?", inventory)
+}
+
+/// Highlights the visible tiles within `range` of the player and waits for one to be clicked.
+pub fn ranged_target(
+  gs: &mut State,
+  ctx: &mut Rltk,
+  range: i32,
+) -> (ItemMenuResult, Option<Point>) {
+  let things = visible_things(&gs.ecs);
+  let player_entity = gs.ecs.fetch::<Entity>();
+  let player_pos = gs.ecs.fetch::<Point>();
+  let viewsheds = gs.ecs.read_storage::<Viewshed>();
+  let bindings = gs.ecs.fetch::<KeyBindings>();
+  let mut cursor = gs.ecs.write_resource::<LookCursor>();
+
+  ctx.print_color(
+    5,
+    0,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "Throw: move the cursor, TAB for things, ENTER or click to throw, ESCAPE to cancel",
+  );
+
+  let origin = camera::origin(&gs.ecs);
+  let mut available_cells = Vec::new();
+  if let Some(visible) = viewsheds.get(*player_entity) {
+    for idx in visible.visible_tiles.iter() {
+      let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
+      if let (true, Some(screen)) = (distance <= range as f32, camera::to_screen(origin, *idx)) {
+        ctx.set_bg(
+          screen.x,
+          screen.y,
+          layers::highlight(RGB::named(rltk::BLUE)),
+        );
+        available_cells.push(*idx);
+      }
+    }
+  } else {
+    return (ItemMenuResult::Cancel, None);
+  }
+
+  // The cursor follows the mouse whenever it moves, and the movement keys otherwise
+  if ctx.mouse_pos() != cursor.mouse || ctx.left_click {
+    cursor.mouse = ctx.mouse_pos();
+    if let Some(tile) = camera::to_map(&gs.ecs, ctx.mouse_point()) {
+      cursor.x = tile.x;
+      cursor.y = tile.y;
+    }
+  }
+  let action = bindings.action(ctx);
+  if let Some((delta_x, delta_y)) = action.and_then(|a| a.direction()) {
+    move_cursor(&gs.ecs, &mut cursor, delta_x, delta_y);
+  } else if ctx.key == Some(VirtualKeyCode::Tab) {
+    let in_range: Vec<Point> = things
+      .into_iter()
+      .filter(|t| available_cells.contains(t))
+      .collect();
+    cycle_cursor(&mut cursor, &in_range);
+  }
+
+  let target = Point::new(cursor.x, cursor.y);
+  let valid = available_cells.contains(&target);
+  if valid {
+    // Preview the flight path
+    for point in rltk::line2d(rltk::LineAlg::Bresenham, *player_pos, target) {
+      if let (true, Some(screen)) = (point != *player_pos, camera::to_screen(origin, point)) {
+        ctx.set_bg(
+          screen.x,
+          screen.y,
+          layers::highlight(RGB::named(rltk::DARK_CYAN)),
+        );
+      }
+    }
+  }
+  if let Some(screen) = camera::to_screen(origin, target) {
+    let colour = if valid { rltk::CYAN } else { rltk::RED };
+    ctx.set_bg(screen.x, screen.y, layers::highlight(RGB::named(colour)));
+  }
+
+  let chosen =
+    ctx.left_click || ctx.key == Some(VirtualKeyCode::Return) || action == Some(Action::Throw);
+  match ctx.key {
+    Some(VirtualKeyCode::Escape) => (ItemMenuResult::Cancel, None),
+    _ if chosen && valid => (ItemMenuResult::Selected, Some(target)),
+    _ if ctx.left_click => (ItemMenuResult::Cancel, None),
+    _ => (ItemMenuResult::NoResponse, None),
+  }
+}
+
+/// Where the cursor sits while looking around the map or picking a target.
+#[derive(Default)]
+pub struct LookCursor {
+  pub x: i32,
+  pub y: i32,
+  /// Where the mouse was last seen, so the cursor only follows it once it moves.
+  pub mouse: (i32, i32),
+}
+
+/// Moves a cursor by a step, keeping it on the part of the map that is on screen.
+fn move_cursor(ecs: &World, cursor: &mut LookCursor, delta_x: i32, delta_y: i32) {
+  let origin = camera::origin(ecs);
+  let map = ecs.fetch::<Map>();
+  let right = (origin.x + camera::VIEW_WIDTH).min(map.width) - 1;
+  let bottom = (origin.y + camera::VIEW_HEIGHT).min(map.height) - 1;
+  cursor.x = (cursor.x + delta_x).clamp(origin.x, right);
+  cursor.y = (cursor.y + delta_y).clamp(origin.y, bottom);
+}
+
+/// Moves the cursor on to the next of `things` after the one it is on, if any.
+fn cycle_cursor(cursor: &mut LookCursor, things: &[Point]) {
+  let here = Point::new(cursor.x, cursor.y);
+  let next = things
+    .iter()
+    .position(|p| *p == here)
+    .map_or(0, |i| (i + 1) % things.len());
+  if let Some(thing) = things.get(next) {
+    cursor.x = thing.x;
+    cursor.y = thing.y;
+  }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum LookResult {
+  Close,
+  Open,
+}
+
+/// Positions of named things the player can see, nearest first.
+fn visible_things(ecs: &World) -> Vec<Point> {
+  let map = ecs.fetch::<Map>();
+  let player_pos = ecs.fetch::<Point>();
+  let names = ecs.read_storage::<Name>();
+  let positions = ecs.read_storage::<Position>();
+  let players = ecs.read_storage::<Player>();
+
+  let mut things: Vec<Point> = (&names, &positions, !&players)
+    .join()
+    .filter(|(_, pos, _)| map.visible_tiles[map.xy_idx(pos.x, pos.y)])
+    .map(|(_, pos, _)| Point::new(pos.x, pos.y))
+    .collect();
+  things.sort_by(|a, b| {
+    let da = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *a);
+    let db = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *b);
+    da.partial_cmp(&db).unwrap()
+  });
+  things.dedup();
+  things
+}
+
+/// Splits text into lines no wider than `width`, breaking between words.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+  let mut lines = Vec::new();
+  let mut line = String::new();
+  for word in text.split_whitespace() {
+    if !line.is_empty() && line.chars().count() + word.chars().count() + 1 > width {
+      lines.push(line);
+      line = String::new();
+    }
+    if !line.is_empty() {
+      line.push(' ');
+    }
+    line.push_str(word);
+  }
+  if !line.is_empty() {
+    lines.push(line);
+  }
+  lines
+}
+
+/// Moves a cursor around the map with the movement keys and describes what is under it.
+pub fn look_mode(gs: &mut State, ctx: &mut Rltk) -> LookResult {
+  let things = visible_things(&gs.ecs);
+  let (x, y) = {
+    let bindings = gs.ecs.fetch::<KeyBindings>();
+    let mut cursor = gs.ecs.write_resource::<LookCursor>();
+    let action = bindings.action(ctx);
+    if let Some(key) = ctx.key {
+      if let Some((delta_x, delta_y)) = action.and_then(|a| a.direction()) {
+        move_cursor(&gs.ecs, &mut cursor, delta_x, delta_y);
+      } else {
+        match key {
+          VirtualKeyCode::Escape => return LookResult::Close,
+          _ if action == Some(Action::Look) => return LookResult::Close,
+          VirtualKeyCode::Tab => cycle_cursor(&mut cursor, &things),
+          _ => {}
+        }
+      }
+    }
+    (cursor.x, cursor.y)
+  };
+
+  ctx.print_color(
+    5,
+    0,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "Look: move the cursor, TAB for things in view, ESCAPE to stop",
+  );
+  if let Some(screen) = camera::to_screen(camera::origin(&gs.ecs), Point::new(x, y)) {
+    ctx.set_bg(
+      screen.x,
+      screen.y,
+      layers::highlight(RGB::named(rltk::CYAN)),
+    );
+  }
+  draw_description(&gs.ecs, ctx, x, y);
+
+  LookResult::Open
+}
+
+/// A panel naming and describing everything on the tile at x, y, plus the terrain.
+fn draw_description(ecs: &World, ctx: &mut Rltk, x: i32, y: i32) {
+  const WIDTH: i32 = 30;
+  let map = ecs.fetch::<Map>();
+  let names = ecs.read_storage::<Name>();
+  let descriptions = ecs.read_storage::<Description>();
+  let doors = ecs.read_storage::<Door>();
+  let renderables = ecs.read_storage::<Renderable>();
+  let positions = ecs.read_storage::<Position>();
+  let entities = ecs.entities();
+
+  let idx = map.xy_idx(x, y);
+  let grey = RGB::named(rltk::GREY);
+  let mut lines: Vec<(String, RGB)> = Vec::new();
+
+  if map.visible_tiles[idx] {
+    for (entity, name, pos) in (&entities, &names, &positions).join() {
+      if pos.x != x || pos.y != y {
+        continue;
+      }
+      let colour = renderables
+        .get(entity)
+        .map_or(RGB::named(rltk::WHITE), |r| r.fg);
+      lines.push((name.name.clone(), colour));
+      if let Some(description) = descriptions.get(entity) {
+        for line in wrap(&description.text, WIDTH as usize - 2) {
+          lines.push((line, RGB::named(rltk::LIGHT_GRAY)));
+        }
+      }
+      if let Some(door) = doors.get(entity) {
+        let state = if door.open {
+          "It is open."
+        } else {
+          "It is closed."
+        };
+        lines.push((state.to_string(), RGB::named(rltk::LIGHT_GRAY)));
+      }
+      lines.push((String::new(), grey));
+    }
+  }
+
+  if !map.revealed_tiles[idx] {
+    lines.push(("Unexplored".to_string(), grey));
+  } else if map.visible_tiles[idx] {
+    lines.push((format!("Terrain: {}", map.tiles[idx].name()), grey));
+  } else {
+    lines.push((
+      format!("Terrain: {} (remembered)", map.tiles[idx].name()),
+      grey,
+    ));
+  }
+
+  // Keep the panel on the other side of the view from the cursor
+  let left = if x - camera::origin(ecs).x > camera::VIEW_WIDTH / 2 {
+    1
+  } else {
+    camera::VIEW_WIDTH - WIDTH - 2
+  };
+  ctx.draw_box(
+    left,
+    2,
+    WIDTH,
+    lines.len() as i32 + 1,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+  );
+  for (y, (text, colour)) in (3..).zip(lines.iter()) {
+    ctx.print_color(left + 1, y, *colour, RGB::named(rltk::BLACK), text);
+  }
+}
+
+const LOG_FILTERS: [Option<LogCategory>; 5] = [
+  None,
+  Some(LogCategory::Flavour),
+  Some(LogCategory::System),
+  Some(LogCategory::Companion),
+  Some(LogCategory::Warning),
+];
+
+/// Scroll position, search text and category filter of the message history screen.
+#[derive(Default)]
+pub struct LogViewer {
+  pub offset: usize,
+  pub search: String,
+  pub searching: bool,
+  pub filter: usize,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum LogViewerResult {
+  Close,
+  Open,
+}
+
+fn key_to_char(key: VirtualKeyCode) -> Option<char> {
+  let letter = rltk::letter_to_option(key);
+  if letter > -1 {
+    return Some((b'a' + letter as u8) as char);
+  }
+  match key {
+    VirtualKeyCode::Key0 => Some('0'),
+    VirtualKeyCode::Key1 => Some('1'),
+    VirtualKeyCode::Key2 => Some('2'),
+    VirtualKeyCode::Key3 => Some('3'),
+    VirtualKeyCode::Key4 => Some('4'),
+    VirtualKeyCode::Key5 => Some('5'),
+    VirtualKeyCode::Key6 => Some('6'),
+    VirtualKeyCode::Key7 => Some('7'),
+    VirtualKeyCode::Key8 => Some('8'),
+    VirtualKeyCode::Key9 => Some('9'),
+    VirtualKeyCode::Space => Some(' '),
+    _ => None,
+  }
+}
+
+/// Full screen view of the whole message history, newest at the bottom.
+pub fn show_log(gs: &mut State, ctx: &mut Rltk) -> LogViewerResult {
+  let log = gs.ecs.fetch::<GameLog>();
+  let bindings = gs.ecs.fetch::<KeyBindings>();
+  let mut viewer = gs.ecs.write_resource::<LogViewer>();
+
+  let filter = LOG_FILTERS[viewer.filter];
+  let search = viewer.search.to_lowercase();
+  let shown: Vec<&LogEntry> = log
+    .entries
+    .iter()
+    .filter(|e| filter.is_none_or(|c| e.category == c))
+    .filter(|e| search.is_empty() || e.text().to_lowercase().contains(&search))
+    .collect();
+
+  let page = 44;
+  let max_offset = shown.len().saturating_sub(page);
+
+  if let Some(key) = ctx.key {
+    if viewer.searching {
+      match key {
+        VirtualKeyCode::Return | VirtualKeyCode::Escape => viewer.searching = false,
+        VirtualKeyCode::Back => {
+          viewer.search.pop();
+        }
+        _ => {
+          if let Some(c) = key_to_char(key) {
+            viewer.search.push(c);
+            viewer.offset = 0;
+          }
+        }
+      }
+    } else {
+      match key {
+        VirtualKeyCode::Escape => return LogViewerResult::Close,
+        _ if bindings.action(ctx) == Some(Action::MessageLog) => return LogViewerResult::Close,
+        VirtualKeyCode::Up | VirtualKeyCode::K => viewer.offset += 1,
+        VirtualKeyCode::Down | VirtualKeyCode::J => viewer.offset = viewer.offset.saturating_sub(1),
+        VirtualKeyCode::PageUp => viewer.offset += page,
+        VirtualKeyCode::PageDown => viewer.offset = viewer.offset.saturating_sub(page),
+        VirtualKeyCode::Home => viewer.offset = max_offset,
+        VirtualKeyCode::End => viewer.offset = 0,
+        VirtualKeyCode::Slash => viewer.searching = true,
+        VirtualKeyCode::Tab => {
+          viewer.filter = (viewer.filter + 1) % LOG_FILTERS.len();
+          viewer.offset = 0;
+        }
+        _ => {}
+      }
+    }
+  }
+  viewer.offset = usize::min(viewer.offset, max_offset);
+
+  ctx.cls();
+  ctx.draw_box(
+    0,
+    0,
+    79,
+    49,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+  );
+  ctx.print_color(
+    3,
+    0,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    format!(
+      " Message History ({} of {}) ",
+      shown.len(),
+      log.entries.len()
+    ),
+  );
+
+  let end = shown.len() - viewer.offset;
+  let start = end.saturating_sub(page);
+  for (y, entry) in (2..).zip(shown[start..end].iter()) {
+    draw_log_entry(ctx, 2, y, entry);
+  }
+
+  let filter_name = filter.map_or("All", |c| c.name());
+  let cursor = if viewer.searching { "_" } else { "" };
+  ctx.print_color(
+    2,
+    47,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+    format!(
+      "Filter: {}   Search: {}{}",
+      filter_name, viewer.search, cursor
+    ),
+  );
+  ctx.print_color(
+    2,
+    49,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    " Up/Down PgUp/PgDn Home/End scroll, / search, TAB filter, ESC close ",
+  );
+
+  LogViewerResult::Open
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum BindingsScreenResult {
+  Close,
+  Open,
+}
+
+/// Lists every action with the keys currently bound to it.
+pub fn show_bindings(gs: &mut State, ctx: &mut Rltk) -> BindingsScreenResult {
+  let bindings = gs.ecs.fetch::<KeyBindings>();
+
+  if let Some(key) = ctx.key {
+    if key == VirtualKeyCode::Escape || bindings.action(ctx) == Some(Action::KeyBindings) {
+      return BindingsScreenResult::Close;
+    }
+  }
+
+  ctx.cls();
+  ctx.draw_box(
+    0,
+    0,
+    79,
+    49,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+  );
+  ctx.print_color(
+    3,
+    0,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    " Key Bindings ",
+  );
+
+  for (y, action) in (2..).zip(ACTIONS.iter()) {
+    ctx.print_color(
+      3,
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      action.description(),
+    );
+    let keys = bindings.keys_for(*action);
+    if keys.is_empty() {
+      ctx.print_color(
+        30,
+        y,
+        RGB::named(rltk::DARK_GRAY),
+        RGB::named(rltk::BLACK),
+        "unbound",
+      );
+    } else {
+      ctx.print_color(
+        30,
+        y,
+        RGB::named(rltk::CYAN),
+        RGB::named(rltk::BLACK),
+        keys.join(", "),
+      );
+    }
+  }
+
+  ctx.print_color(
+    3,
+    ACTIONS.len() as i32 + 3,
+    RGB::named(rltk::LIGHT_GRAY),
+    RGB::named(rltk::BLACK),
+    "Hold Shift with a movement key to run. Bindings live in raws/keybindings.json.",
+  );
+  ctx.print_color(
+    3,
+    49,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    " ESCAPE to close ",
+  );
+
+  BindingsScreenResult::Open
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum MenuResult {
+  NoSelection { selected: usize },
+  Selected { selected: usize },
+  Cancel,
+}
+
+/// Draws a list of entries centred on the screen and handles moving through it with the arrow
+/// keys, picking with Enter, or hovering and clicking with the mouse. Disabled entries are
+/// greyed out and skipped.
+fn menu(
+  ctx: &mut Rltk,
+  title: &str,
+  entries: &[(String, bool)],
+  selected: usize,
+  top: i32,
+) -> MenuResult {
+  let width = entries
+    .iter()
+    .map(|(text, _)| text.chars().count())
+    .chain(std::iter::once(title.chars().count()))
+    .max()
+    .unwrap_or(0) as i32
+    + 6;
+  let left = 40 - width / 2;
+  ctx.draw_box(
+    left,
+    top,
+    width,
+    entries.len() as i32 + 3,
+    RGB::named(rltk::WHITE),
+    RGB::named(rltk::BLACK),
+  );
+  ctx.print_color_centered(
+    top,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    format!(" {} ", title),
+  );
+
+  let mut selected = selected;
+  let enabled: Vec<usize> = (0..entries.len()).filter(|i| entries[*i].1).collect();
+
+  // The mouse picks whatever it is over
+  let (mouse_x, mouse_y) = ctx.mouse_pos();
+  let hovered = (0..entries.len()).find(|i| {
+    entries[*i].1 && mouse_y == top + 2 + *i as i32 && mouse_x > left && mouse_x < left + width
+  });
+  if let Some(hovered) = hovered {
+    selected = hovered;
+    if ctx.left_click {
+      return MenuResult::Selected { selected };
+    }
+  }
+
+  if let Some(key) = ctx.key {
+    let position = enabled.iter().position(|i| *i == selected).unwrap_or(0);
+    match key {
+      VirtualKeyCode::Escape => return MenuResult::Cancel,
+      VirtualKeyCode::Up | VirtualKeyCode::K => {
+        selected = enabled[(position + enabled.len() - 1) % enabled.len()];
+      }
+      VirtualKeyCode::Down | VirtualKeyCode::J => {
+        selected = enabled[(position + 1) % enabled.len()];
+      }
+      VirtualKeyCode::Return => return MenuResult::Selected { selected },
+      _ => {}
+    }
+  }
+
+  for (i, (text, is_enabled)) in entries.iter().enumerate() {
+    let fg = if !is_enabled {
+      RGB::named(rltk::DARK_GRAY)
+    } else if i == selected {
+      RGB::named(rltk::MAGENTA)
+    } else {
+      RGB::named(rltk::WHITE)
+    };
+    ctx.print_color_centered(top + 2 + i as i32, fg, RGB::named(rltk::BLACK), text);
+  }
+
+  MenuResult::NoSelection { selected }
+}
+
+/// Something picked from the main or pause menu.
+#[derive(PartialEq, Copy, Clone)]
+pub enum MenuAction {
+  NewGame,
+  Continue,
+  Resume,
+  Options,
+  EndGame,
+  QuitToMainMenu,
+  Quit,
+}
+
+impl MenuAction {
+  pub fn label(&self) -> &'static str {
+    match self {
+      MenuAction::NewGame => "New Game",
+      MenuAction::Continue => "Continue",
+      MenuAction::Resume => "Resume",
+      MenuAction::Options => "Options",
+      MenuAction::EndGame => "End Game",
+      MenuAction::QuitToMainMenu => "Quit to Main Menu",
+      MenuAction::Quit => "Quit",
+    }
+  }
+}
+
+/// Where an action sits in a menu, for returning to it with the same entry highlighted.
+pub fn menu_position(menu: &[MenuAction], action: MenuAction) -> usize {
+  menu.iter().position(|a| *a == action).unwrap_or(0)
+}
+
+pub const MAIN_MENU: [MenuAction; 4] = [
+  MenuAction::NewGame,
+  MenuAction::Continue,
+  MenuAction::Options,
+  MenuAction::Quit,
+];
+
+/// The title screen. Continue is only offered while a game is under way.
+pub fn main_menu(ctx: &mut Rltk, selected: usize, can_continue: bool) -> MenuResult {
+  ctx.cls();
+  ctx.print_color_centered(
+    12,
+    RGB::named(rltk::MAGENTA),
+    RGB::named(rltk::BLACK),
+    "M O O N S H O T",
+  );
+  ctx.print_color_centered(
+    14,
+    RGB::named(rltk::GRAY),
+    RGB::named(rltk::BLACK),
+    "Something is scratching at the door.",
+  );
+
+  let entries: Vec<(String, bool)> = MAIN_MENU
+    .iter()
+    .map(|action| {
+      let enabled = *action != MenuAction::Continue || can_continue;
+      (action.label().to_string(), enabled)
+    })
+    .collect();
+  menu(ctx, "Main Menu", &entries, selected, 18)
+}
+
+pub const PAUSE_MENU: [MenuAction; 4] = [
+  MenuAction::Resume,
+  MenuAction::Options,
+  MenuAction::EndGame,
+  MenuAction::QuitToMainMenu,
+];
+
+/// The menu Escape brings up over the map.
+pub fn pause_menu(ctx: &mut Rltk, selected: usize) -> MenuResult {
+  let entries: Vec<(String, bool)> = PAUSE_MENU
+    .iter()
+    .map(|action| (action.label().to_string(), true))
+    .collect();
+  menu(ctx, "Paused", &entries, selected, 15)
+}
+
+/// Settings that can be changed while playing. Picking an entry toggles it; the last one goes
+/// back to whichever menu opened this.
+pub fn options_menu(ecs: &World, ctx: &mut Rltk, selected: usize) -> MenuResult {
+  let overlay = ecs.fetch::<DebugOverlay>();
+  let settings = ecs.fetch::<Settings>();
+  let on_off = |on: bool| if on { "On" } else { "Off" };
+  let restart = if settings.font == settings.font_in_use {
+    ""
+  } else {
+    " (on restart)"
+  };
+  let entries = vec![
+    (format!("Scent overlay: {}", on_off(overlay.scent)), true),
+    (format!("Tileset: {}", settings.tileset.name()), true),
+    (format!("Scanlines: {}", on_off(settings.scanlines)), true),
+    (
+      format!("Screen burn: {}", on_off(settings.screen_burn)),
+      settings.scanlines,
+    ),
+    (format!("Font: {}{}", settings.font.name(), restart), true),
+    (
+      format!("Animated terrain: {}", on_off(!settings.reduce_motion)),
+      true,
+    ),
+    ("Back".to_string(), true),
+  ];
+  menu(ctx, "Options", &entries, selected, 15)
+}
+
+/// What became of the day out, with the running totals and achievements earned.
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> bool {
+  let stats = ecs.fetch::<Statistics>();
+  let achievements = ecs.fetch::<Achievements>();
+  let log = ecs.fetch::<GameLog>();
+
+  ctx.cls();
+  ctx.print_color_centered(
+    10,
+    RGB::named(rltk::MAGENTA),
+    RGB::named(rltk::BLACK),
+    "The day is done",
+  );
+  ctx.print_color_centered(
+    12,
+    RGB::named(rltk::GRAY),
+    RGB::named(rltk::BLACK),
+    "You and Moonshot head home for the night.",
+  );
+
+  let totals = [
+    format!("Turns passed:   {}", log.turn),
+    format!("Steps taken:    {}", stats.steps),
+    format!("Doors opened:   {}", stats.doors_opened),
+    format!("Sounds heard:   {}", stats.noises_heard),
+    format!("Items thrown:   {}", stats.items_thrown),
+    format!("Games of fetch: {}", stats.fetches),
+  ];
+  for (y, line) in (15..).zip(totals.iter()) {
+    ctx.print_color(
+      30,
+      y,
+      RGB::named(rltk::WHITE),
+      RGB::named(rltk::BLACK),
+      line,
+    );
+  }
+
+  let top = 16 + totals.len() as i32;
+  if achievements.unlocked.is_empty() {
+    ctx.print_color_centered(
+      top,
+      RGB::named(rltk::DARK_GRAY),
+      RGB::named(rltk::BLACK),
+      "No achievements this time.",
+    );
+  } else {
+    ctx.print_color_centered(
+      top,
+      RGB::named(rltk::YELLOW),
+      RGB::named(rltk::BLACK),
+      "Achievements",
+    );
+    for (y, name) in (top + 1..).zip(achievements.unlocked.iter()) {
+      ctx.print_color_centered(y, RGB::named(rltk::GOLD), RGB::named(rltk::BLACK), name);
+    }
+  }
+
+  ctx.print_color_centered(
+    45,
+    RGB::named(rltk::YELLOW),
+    RGB::named(rltk::BLACK),
+    "Press any key or click to return to the main menu",
+  );
+
+  ctx.key.is_some() || ctx.left_click
+}
@@ -0,0 +1,70 @@
+use super::{GameEvent, GameEvents, InBackpack, Position, WantsToDrop, WantsToPickup};
+use specs::prelude::*;
+
+pub struct ItemCollectionSystem {}
+
+impl<'a> System<'a> for ItemCollectionSystem {
+  type SystemData = (
+    WriteExpect<'a, GameEvents>,
+    WriteStorage<'a, WantsToPickup>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, InBackpack>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (mut events, mut wants_pickup, mut positions, mut backpack) = data;
+
+    for pickup in wants_pickup.join() {
+      positions.remove(pickup.item);
+      backpack
+        .insert(
+          pickup.item,
+          InBackpack {
+            owner: pickup.collected_by,
+          },
+        )
+        .expect("Unable to insert backpack entry");
+
+      events.emit(GameEvent::PickedUp {
+        entity: pickup.collected_by,
+        item: pickup.item,
+      });
+    }
+
+    wants_pickup.clear();
+  }
+}
+
+pub struct ItemDropSystem {}
+
+impl<'a> System<'a> for ItemDropSystem {
+  type SystemData = (
+    WriteExpect<'a, GameEvents>,
+    Entities<'a>,
+    WriteStorage<'a, WantsToDrop>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, InBackpack>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (mut events, entities, mut wants_drop, mut positions, mut backpack) = data;
+
+    for (entity, to_drop) in (&entities, &wants_drop).join() {
+      let (x, y) = match positions.get(entity) {
+        Some(pos) => (pos.x, pos.y),
+        None => continue,
+      };
+      positions
+        .insert(to_drop.item, Position { x, y })
+        .expect("Unable to insert position");
+      backpack.remove(to_drop.item);
+
+      events.emit(GameEvent::Dropped {
+        entity,
+        item: to_drop.item,
+      });
+    }
+
+    wants_drop.clear();
+  }
+}
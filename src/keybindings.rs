@@ -0,0 +1,405 @@
+use rltk::{Rltk, VirtualKeyCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Everything the player can do from the map with a key press.
+#[derive(Deserialize, PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+  MoveN,
+  MoveS,
+  MoveE,
+  MoveW,
+  #[serde(rename = "move_ne")]
+  MoveNE,
+  #[serde(rename = "move_nw")]
+  MoveNW,
+  #[serde(rename = "move_se")]
+  MoveSE,
+  #[serde(rename = "move_sw")]
+  MoveSW,
+  Wait,
+  Rest,
+  Pickup,
+  Inventory,
+  Drop,
+  Throw,
+  Look,
+  Explore,
+  Minimap,
+  MessageLog,
+  KeyBindings,
+  ScentOverlay,
+  Pause,
+}
+
+/// Every action, in the order the bindings screen lists them.
+pub const ACTIONS: [Action; 21] = [
+  Action::MoveN,
+  Action::MoveS,
+  Action::MoveE,
+  Action::MoveW,
+  Action::MoveNE,
+  Action::MoveNW,
+  Action::MoveSE,
+  Action::MoveSW,
+  Action::Wait,
+  Action::Rest,
+  Action::Pickup,
+  Action::Inventory,
+  Action::Drop,
+  Action::Throw,
+  Action::Look,
+  Action::Explore,
+  Action::Minimap,
+  Action::MessageLog,
+  Action::KeyBindings,
+  Action::ScentOverlay,
+  Action::Pause,
+];
+
+impl Action {
+  pub fn description(&self) -> &'static str {
+    match self {
+      Action::MoveN => "Move north",
+      Action::MoveS => "Move south",
+      Action::MoveE => "Move east",
+      Action::MoveW => "Move west",
+      Action::MoveNE => "Move north-east",
+      Action::MoveNW => "Move north-west",
+      Action::MoveSE => "Move south-east",
+      Action::MoveSW => "Move south-west",
+      Action::Wait => "Wait a turn",
+      Action::Rest => "Rest until disturbed",
+      Action::Pickup => "Pick up an item",
+      Action::Inventory => "Inventory",
+      Action::Drop => "Drop an item",
+      Action::Throw => "Throw an item",
+      Action::Look => "Look around",
+      Action::Explore => "Explore automatically",
+      Action::Minimap => "Toggle minimap",
+      Action::MessageLog => "Message history",
+      Action::KeyBindings => "Key bindings",
+      Action::ScentOverlay => "Scent overlay (debug)",
+      Action::Pause => "Pause menu",
+    }
+  }
+
+  /// The step a movement action takes, or None for everything else.
+  pub fn direction(&self) -> Option<(i32, i32)> {
+    match self {
+      Action::MoveN => Some((0, -1)),
+      Action::MoveS => Some((0, 1)),
+      Action::MoveE => Some((1, 0)),
+      Action::MoveW => Some((-1, 0)),
+      Action::MoveNE => Some((1, -1)),
+      Action::MoveNW => Some((-1, -1)),
+      Action::MoveSE => Some((1, 1)),
+      Action::MoveSW => Some((-1, 1)),
+      _ => None,
+    }
+  }
+}
+
+/// Keys that can appear in the bindings file, by their `VirtualKeyCode` name.
+const BINDABLE_KEYS: &[VirtualKeyCode] = &[
+  VirtualKeyCode::A,
+  VirtualKeyCode::B,
+  VirtualKeyCode::C,
+  VirtualKeyCode::D,
+  VirtualKeyCode::E,
+  VirtualKeyCode::F,
+  VirtualKeyCode::G,
+  VirtualKeyCode::H,
+  VirtualKeyCode::I,
+  VirtualKeyCode::J,
+  VirtualKeyCode::K,
+  VirtualKeyCode::L,
+  VirtualKeyCode::M,
+  VirtualKeyCode::N,
+  VirtualKeyCode::O,
+  VirtualKeyCode::P,
+  VirtualKeyCode::Q,
+  VirtualKeyCode::R,
+  VirtualKeyCode::S,
+  VirtualKeyCode::T,
+  VirtualKeyCode::U,
+  VirtualKeyCode::V,
+  VirtualKeyCode::W,
+  VirtualKeyCode::X,
+  VirtualKeyCode::Y,
+  VirtualKeyCode::Z,
+  VirtualKeyCode::Key0,
+  VirtualKeyCode::Key1,
+  VirtualKeyCode::Key2,
+  VirtualKeyCode::Key3,
+  VirtualKeyCode::Key4,
+  VirtualKeyCode::Key5,
+  VirtualKeyCode::Key6,
+  VirtualKeyCode::Key7,
+  VirtualKeyCode::Key8,
+  VirtualKeyCode::Key9,
+  VirtualKeyCode::Numpad0,
+  VirtualKeyCode::Numpad1,
+  VirtualKeyCode::Numpad2,
+  VirtualKeyCode::Numpad3,
+  VirtualKeyCode::Numpad4,
+  VirtualKeyCode::Numpad5,
+  VirtualKeyCode::Numpad6,
+  VirtualKeyCode::Numpad7,
+  VirtualKeyCode::Numpad8,
+  VirtualKeyCode::Numpad9,
+  VirtualKeyCode::Up,
+  VirtualKeyCode::Down,
+  VirtualKeyCode::Left,
+  VirtualKeyCode::Right,
+  VirtualKeyCode::Home,
+  VirtualKeyCode::End,
+  VirtualKeyCode::PageUp,
+  VirtualKeyCode::PageDown,
+  VirtualKeyCode::F1,
+  VirtualKeyCode::F2,
+  VirtualKeyCode::F3,
+  VirtualKeyCode::F4,
+  VirtualKeyCode::F5,
+  VirtualKeyCode::F6,
+  VirtualKeyCode::F7,
+  VirtualKeyCode::F8,
+  VirtualKeyCode::F9,
+  VirtualKeyCode::F10,
+  VirtualKeyCode::F11,
+  VirtualKeyCode::F12,
+  VirtualKeyCode::Space,
+  VirtualKeyCode::Return,
+  VirtualKeyCode::Tab,
+  VirtualKeyCode::Period,
+  VirtualKeyCode::Comma,
+  VirtualKeyCode::Slash,
+  VirtualKeyCode::Semicolon,
+  VirtualKeyCode::Escape,
+];
+
+/// A key together with the modifiers held down with it.
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct KeyChord {
+  pub key: VirtualKeyCode,
+  pub shift: bool,
+  pub control: bool,
+  pub alt: bool,
+}
+
+impl KeyChord {
+  /// Parses names such as "H", "Numpad8" or "Shift+Slash".
+  fn parse(text: &str) -> Result<KeyChord, String> {
+    let mut parts: Vec<&str> = text.split('+').collect();
+    let key_name = parts.pop().unwrap_or_default();
+    let key = BINDABLE_KEYS
+      .iter()
+      .find(|k| format!("{:?}", k) == key_name)
+      .copied()
+      .ok_or_else(|| format!("unknown key '{}'", key_name))?;
+
+    let mut chord = KeyChord {
+      key,
+      shift: false,
+      control: false,
+      alt: false,
+    };
+    for modifier in parts {
+      match modifier {
+        "Shift" => chord.shift = true,
+        "Ctrl" => chord.control = true,
+        "Alt" => chord.alt = true,
+        _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, text)),
+      }
+    }
+    Ok(chord)
+  }
+
+  pub fn name(&self) -> String {
+    let mut name = String::new();
+    if self.control {
+      name.push_str("Ctrl+");
+    }
+    if self.alt {
+      name.push_str("Alt+");
+    }
+    if self.shift {
+      name.push_str("Shift+");
+    }
+    name.push_str(&format!("{:?}", self.key));
+    name
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingsFile {
+  bindings: Vec<BindingTemplate>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingTemplate {
+  action: Action,
+  keys: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct KeyBindings {
+  actions: HashMap<KeyChord, Action>,
+  /// Every binding in the order the file lists them.
+  listed: Vec<(KeyChord, Action)>,
+}
+
+impl KeyBindings {
+  /// Reads a bindings file, refusing it if any chord is bound to two different actions.
+  pub fn load(data: &str) -> Result<KeyBindings, String> {
+    let file: BindingsFile = serde_json::from_str(data).map_err(|e| e.to_string())?;
+
+    let mut actions = HashMap::new();
+    let mut listed = Vec::new();
+    for binding in file.bindings.iter() {
+      for key in binding.keys.iter() {
+        let chord = KeyChord::parse(key)?;
+        if let Some(existing) = actions.insert(chord, binding.action) {
+          if existing != binding.action {
+            return Err(format!(
+              "'{}' is bound to both '{}' and '{}'",
+              chord.name(),
+              existing.description(),
+              binding.action.description()
+            ));
+          }
+        } else {
+          listed.push((chord, binding.action));
+        }
+      }
+    }
+
+    Ok(KeyBindings { actions, listed })
+  }
+
+  /// The action for the key pressed this frame. Holding shift on a movement key that has no
+  /// shifted binding of its own still counts as that movement, so it can be used to run.
+  pub fn action(&self, ctx: &Rltk) -> Option<Action> {
+    let chord = KeyChord {
+      key: ctx.key?,
+      shift: ctx.shift,
+      control: ctx.control,
+      alt: ctx.alt,
+    };
+    if let Some(action) = self.actions.get(&chord) {
+      return Some(*action);
+    }
+    if chord.shift {
+      let unshifted = KeyChord {
+        shift: false,
+        ..chord
+      };
+      return self
+        .actions
+        .get(&unshifted)
+        .copied()
+        .filter(|a| a.direction().is_some());
+    }
+    None
+  }
+
+  /// The names of every chord bound to `action`.
+  pub fn keys_for(&self, action: Action) -> Vec<String> {
+    self
+      .listed
+      .iter()
+      .filter(|(_, a)| *a == action)
+      .map(|(chord, _)| chord.name())
+      .collect()
+  }
+}
+
+rltk::embedded_resource!(BINDINGS_FILE, "../raws/keybindings.json");
+
+/// Where players edit their bindings. Read when the game starts, so changes need no rebuild.
+const BINDINGS_PATH: &str = "raws/keybindings.json";
+
+/// Reads the key bindings file, falling back to the copy built into the game if there is none.
+pub fn load_bindings() -> Result<KeyBindings, String> {
+  match std::fs::read_to_string(BINDINGS_PATH) {
+    Ok(text) => KeyBindings::load(&text).map_err(|e| format!("{}: {}", BINDINGS_PATH, e)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => default_bindings(),
+    Err(e) => Err(format!("{}: {}", BINDINGS_PATH, e)),
+  }
+}
+
+/// Parses the bindings the game was built with.
+fn default_bindings() -> Result<KeyBindings, String> {
+  rltk::link_resource!(BINDINGS_FILE, "../raws/keybindings.json");
+
+  let data = rltk::embedding::EMBED
+    .lock()
+    .get_resource("../raws/keybindings.json".to_string())
+    .ok_or_else(|| "raws/keybindings.json: not embedded in the build".to_string())?;
+  let text = std::str::from_utf8(data).map_err(|e| format!("raws/keybindings.json: {}", e))?;
+
+  KeyBindings::load(text).map_err(|e| format!("raws/keybindings.json (built in): {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn the_default_bindings_load() {
+    let bindings = default_bindings().unwrap();
+    for action in ACTIONS.iter() {
+      assert!(
+        !bindings.keys_for(*action).is_empty(),
+        "{:?} has no key",
+        action
+      );
+    }
+  }
+
+  #[test]
+  fn a_key_bound_to_two_actions_is_refused() {
+    let data = r#"{ "bindings": [
+      { "action": "wait", "keys": ["Period"] },
+      { "action": "rest", "keys": ["R", "Period"] }
+    ] }"#;
+    assert_eq!(
+      KeyBindings::load(data).err().unwrap(),
+      "'Period' is bound to both 'Wait a turn' and 'Rest until disturbed'"
+    );
+  }
+
+  #[test]
+  fn modifiers_make_a_different_chord() {
+    let data = r#"{ "bindings": [
+      { "action": "look", "keys": ["Slash"] },
+      { "action": "key_bindings", "keys": ["Shift+Slash", "Slash"] }
+    ] }"#;
+    assert!(KeyBindings::load(data).is_err());
+
+    let data = r#"{ "bindings": [
+      { "action": "look", "keys": ["Slash", "Slash"] },
+      { "action": "key_bindings", "keys": ["Shift+Slash"] }
+    ] }"#;
+    let bindings = KeyBindings::load(data).unwrap();
+    assert_eq!(bindings.keys_for(Action::Look), vec!["Slash"]);
+    assert_eq!(bindings.keys_for(Action::KeyBindings), vec!["Shift+Slash"]);
+  }
+
+  #[test]
+  fn unknown_keys_are_refused() {
+    let data = r#"{ "bindings": [ { "action": "wait", "keys": ["Hyper+W"] } ] }"#;
+    assert_eq!(
+      KeyBindings::load(data).err().unwrap(),
+      "unknown modifier 'Hyper' in 'Hyper+W'"
+    );
+    let data = r#"{ "bindings": [ { "action": "wait", "keys": ["Banana"] } ] }"#;
+    assert_eq!(
+      KeyBindings::load(data).err().unwrap(),
+      "unknown key 'Banana'"
+    );
+  }
+}
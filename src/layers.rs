@@ -0,0 +1,41 @@
+use rltk::{Rltk, RGB, RGBA};
+
+// The consoles the screen is built from, bottom to top. Each keeps its own glyph and colours
+// for every cell, so what is drawn on one layer never overwrites another.
+
+/// Map glyphs and their colours.
+pub const TERRAIN: usize = 0;
+/// Sprites from the tile sheet, for terrain and entities alike.
+pub const SPRITES: usize = 1;
+/// Entity glyphs and short-lived effects.
+pub const ENTITIES: usize = 2;
+/// Panels, menus, tooltips and highlights.
+pub const UI: usize = 3;
+
+const LAYERS: [usize; 4] = [TERRAIN, SPRITES, ENTITIES, UI];
+
+/// Clears every layer, leaving the interface layer active.
+pub fn clear_all(ctx: &mut Rltk) {
+  for layer in LAYERS.iter() {
+    ctx.set_active_console(*layer);
+    ctx.cls();
+  }
+}
+
+/// Draws on another layer, then returns to the one that was active.
+pub fn draw_on(ctx: &mut Rltk, layer: usize, draw: impl FnOnce(&mut Rltk)) {
+  let previous = ctx.active_console;
+  ctx.set_active_console(layer);
+  draw(ctx);
+  ctx.set_active_console(previous);
+}
+
+/// A background for the interface layer that lets the map beneath show through.
+pub fn highlight(colour: RGB) -> RGBA {
+  colour.to_rgba(0.5)
+}
+
+/// A fully see-through background, for glyphs that should sit on top of whatever is beneath.
+pub fn clear() -> RGBA {
+  RGBA::from_f32(0.0, 0.0, 0.0, 0.0)
+}
@@ -1,26 +1,69 @@
+#[macro_use]
+extern crate lazy_static;
+
 use rltk::Point;
-use rltk::{console, GameState, Rltk, RGB};
+use rltk::{GameState, Rltk};
 use specs::prelude::*;
 
+mod camera;
 mod components;
+mod event_consumers;
+mod gameevents;
 mod gamelog;
 mod gui;
+mod inventory_system;
+mod keybindings;
+mod layers;
 mod map;
 mod map_indexing_system;
+mod minimap;
 mod moonshot_ai;
+mod noise_system;
+mod particle_system;
 mod player;
+mod random_table;
+mod raws;
 mod rect;
+mod scent_system;
+mod settings;
 mod spawners;
+mod throw_system;
+mod tileset;
+mod travel;
 mod visibility_system;
+mod water_system;
+mod weather;
+mod wildlife_ai;
 
+use camera::{VIEW_HEIGHT, VIEW_WIDTH};
 use components::*;
+use event_consumers::{
+    AchievementSystem, Achievements, EventLogSystem, EventParticleSystem, EventSoundSystem,
+    Statistics, StatisticsSystem,
+};
+use gameevents::{GameEvent, GameEvents};
 use gamelog::*;
+use gui::MenuAction;
+use inventory_system::{ItemCollectionSystem, ItemDropSystem};
+use keybindings::{Action, KeyBindings, ACTIONS};
 use map::*;
 use map_indexing_system::MapIndexingSystem;
+use minimap::Minimap;
 use moonshot_ai::*;
+use noise_system::{HearingSystem, NoiseQueue};
+use particle_system::{ParticleBuilder, ParticleSpawnSystem};
 use player::*;
+use random_table::RandomTable;
 use rect::Rect;
+use scent_system::ScentSystem;
+use settings::Settings;
+use throw_system::{animate_projectiles, ThrowSystem};
+use tileset::Tileset;
+use travel::{DisturbanceSystem, PathPreview, Travel};
 use visibility_system::VisibilitySystem;
+use water_system::WaterSystem;
+use weather::{Weather, WeatherSystem};
+use wildlife_ai::WildlifeAI;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
@@ -28,10 +71,24 @@ pub enum RunState {
     PreRun,
     PlayerTurn,
     NpcTurn,
+    ShowInventory,
+    ShowDropItem,
+    ShowThrowItem,
+    ShowTargeting { range: i32, item: Entity },
+    ThrowAnimation,
+    ShowLog,
+    LookMode,
+    ShowBindings,
+    MainMenu { selection: usize },
+    PauseMenu { selection: usize },
+    Options { selection: usize, paused: bool },
+    GameOver,
 }
 
 pub struct State {
     pub ecs: World,
+    /// Whether there is a game to go back to from the main menu.
+    pub in_progress: bool,
 }
 
 impl State {
@@ -40,36 +97,360 @@ impl State {
         vis.run_now(&self.ecs);
         let mut moon = MoonshotAI {};
         moon.run_now(&self.ecs);
+        let mut wildlife = WildlifeAI {};
+        wildlife.run_now(&self.ecs);
+        let mut water = WaterSystem {};
+        water.run_now(&self.ecs);
+        let mut weather = WeatherSystem {};
+        weather.run_now(&self.ecs);
+        let mut sounds = EventSoundSystem {};
+        sounds.run_now(&self.ecs);
+        let mut effects = EventParticleSystem {};
+        effects.run_now(&self.ecs);
+        let mut particles = ParticleSpawnSystem {};
+        particles.run_now(&self.ecs);
+        let mut hearing = HearingSystem {};
+        hearing.run_now(&self.ecs);
+        let mut disturbance = DisturbanceSystem {};
+        disturbance.run_now(&self.ecs);
+        let mut scent = ScentSystem {};
+        scent.run_now(&self.ecs);
+        let mut throw = ThrowSystem {};
+        throw.run_now(&self.ecs);
+        let mut pickup = ItemCollectionSystem {};
+        pickup.run_now(&self.ecs);
+        let mut drop = ItemDropSystem {};
+        drop.run_now(&self.ecs);
+        let mut eventlog = EventLogSystem {};
+        eventlog.run_now(&self.ecs);
+        let mut stats = StatisticsSystem {};
+        stats.run_now(&self.ecs);
+        let mut achievements = AchievementSystem {};
+        achievements.run_now(&self.ecs);
+        self.ecs.write_resource::<GameEvents>().events.clear();
         let mut mapindex = MapIndexingSystem {};
         mapindex.run_now(&self.ecs);
         self.ecs.maintain();
     }
+
+    /// Throws away whatever game was going on and sets up a fresh one.
+    fn new_game(&mut self) {
+        self.ecs.delete_all();
+
+        let map: Map = Map::test_map();
+        let (player_x, player_y) = (35, 26);
+
+        self.ecs.insert(map);
+        self.ecs.insert(Point::new(player_x, player_y));
+        self.ecs.insert(NoiseQueue::default());
+        self.ecs.insert(ParticleBuilder::default());
+        self.ecs.insert(GameEvents::default());
+        self.ecs.insert(Statistics::default());
+        self.ecs.insert(Achievements::default());
+        self.ecs.insert(Travel::default());
+        self.ecs.insert(PathPreview::default());
+        self.ecs.insert(Weather::default());
+
+        // Spawn entities
+        raws::spawn_named(&mut self.ecs, "door", 38, 29);
+        let player_entity = raws::spawn_named(&mut self.ecs, "player", player_x, player_y)
+            .expect("The player template is missing from the raws");
+        raws::spawn_named(&mut self.ecs, "moonshot", 37, 30);
+        raws::spawn_named(&mut self.ecs, "lantern", 40, 25);
+        raws::spawn_named(&mut self.ecs, "dog_treats", 36, 28);
+        raws::spawn_named(&mut self.ecs, "ball", 44, 33);
+        raws::spawn_named(&mut self.ecs, "key", 21, 19);
+        raws::spawn_named(&mut self.ecs, "stick", 46, 26);
+        self.ecs.insert(player_entity);
+        spawners::spawn_wildlife(&mut self.ecs);
+
+        // Add Gamelog
+        let mut gamelog = GameLog::default();
+        gamelog.add("You wake to the sound of scratching coming from the door".to_string());
+        self.ecs.insert(gamelog);
+
+        self.in_progress = true;
+    }
 }
 
 impl GameState for State {
     fn tick(&mut self, ctx: &mut Rltk) {
-        ctx.cls();
+        layers::clear_all(ctx);
+        particle_system::cull_dead_particles(&mut self.ecs, ctx.frame_time_ms);
+        self.ecs
+            .write_resource::<TerrainAnimation>()
+            .advance(ctx.frame_time_ms);
+
         let mut newrunstate;
         {
             let runstate = self.ecs.fetch::<RunState>();
             newrunstate = *runstate;
         }
 
+        match newrunstate {
+            RunState::MainMenu { .. }
+            | RunState::GameOver
+            | RunState::ShowLog
+            | RunState::ShowBindings => {}
+            RunState::Options { paused: false, .. } => {}
+            _ => {
+                ctx.set_active_console(layers::TERRAIN);
+                draw_map(&self.ecs, ctx);
+                ctx.set_active_console(layers::ENTITIES);
+
+                let origin = camera::origin(&self.ecs);
+                let positions = self.ecs.read_storage::<Position>();
+                let renderables = self.ecs.read_storage::<Renderable>();
+                let map = self.ecs.fetch::<Map>();
+                let tileset = self.ecs.fetch::<Settings>().tileset;
+
+                let mut data = (&positions, &renderables).join().collect::<Vec<_>>();
+                data.sort_by_key(|&a| std::cmp::Reverse(a.1.render_order));
+
+                for (pos, render) in data.iter() {
+                    let idx = map.xy_idx(pos.x, pos.y);
+                    let screen = camera::to_screen(origin, Point::new(pos.x, pos.y));
+                    if let (true, Some(screen)) = (map.visible_tiles[idx], screen) {
+                        match tileset.sprite(render.sprite) {
+                            Some(sprite) => {
+                                // Let a sprite floor show around the sprite, but hide a glyph
+                                let bg = if tileset.sprite(map.tiles[idx].sprite()).is_some() {
+                                    layers::clear()
+                                } else {
+                                    render.bg
+                                };
+                                tileset::draw_sprite(ctx, screen.x, screen.y, sprite, true, bg)
+                            }
+                            None => ctx.set(screen.x, screen.y, render.fg, render.bg, render.glyph),
+                        }
+                    }
+                }
+
+                ctx.set_active_console(layers::UI);
+                weather::draw_weather(&self.ecs, ctx);
+                gui::draw_ui(&self.ecs, ctx);
+            }
+        }
+
         match newrunstate {
             RunState::PreRun => {
                 self.run_systems();
                 newrunstate = RunState::AwaitingInput;
             }
             RunState::AwaitingInput => {
-                newrunstate = player_input(self, ctx);
+                if ctx.key.is_none() && travel::is_travelling(&self.ecs) {
+                    newrunstate = travel::travel_step(&mut self.ecs);
+                } else {
+                    travel::stop_travel(&mut self.ecs);
+                    newrunstate = player_input(self, ctx);
+                }
             }
             RunState::PlayerTurn => {
                 self.run_systems();
-                newrunstate = RunState::NpcTurn;
+                if animate_projectiles(&mut self.ecs, 0.0) {
+                    newrunstate = RunState::ThrowAnimation;
+                } else {
+                    newrunstate = RunState::NpcTurn;
+                }
+            }
+            RunState::ThrowAnimation => {
+                if !animate_projectiles(&mut self.ecs, ctx.frame_time_ms) {
+                    newrunstate = RunState::NpcTurn;
+                }
             }
             RunState::NpcTurn => {
                 self.run_systems();
-                newrunstate = RunState::AwaitingInput;
+                self.ecs.write_resource::<GameLog>().turn += 1;
+                newrunstate = if player::lose_turn(&mut self.ecs) {
+                    RunState::NpcTurn
+                } else {
+                    RunState::AwaitingInput
+                };
+            }
+            RunState::ShowInventory => {
+                let (result, item) = gui::show_inventory(self, ctx);
+                match result {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let names = self.ecs.read_storage::<Name>();
+                        let mut gamelog = self.ecs.write_resource::<GameLog>();
+                        if let Some(name) = item.and_then(|item| names.get(item)) {
+                            gamelog.add(format!(
+                                "You turn the {} over in your hands.",
+                                name.name
+                            ));
+                        }
+                        newrunstate = RunState::AwaitingInput;
+                    }
+                }
+            }
+            RunState::ShowDropItem => {
+                let (result, item) = gui::drop_item_menu(self, ctx);
+                match result {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let player_entity = self.ecs.fetch::<Entity>();
+                        let mut intent = self.ecs.write_storage::<WantsToDrop>();
+                        intent
+                            .insert(
+                                *player_entity,
+                                WantsToDrop {
+                                    item: item.unwrap(),
+                                },
+                            )
+                            .expect("Unable to insert intent");
+                        newrunstate = RunState::PlayerTurn;
+                    }
+                }
+            }
+            RunState::LookMode => {
+                if gui::look_mode(self, ctx) == gui::LookResult::Close {
+                    newrunstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::MainMenu { selection } => {
+                match gui::main_menu(ctx, selection, self.in_progress) {
+                    gui::MenuResult::NoSelection { selected } => {
+                        newrunstate = RunState::MainMenu {
+                            selection: selected,
+                        }
+                    }
+                    gui::MenuResult::Cancel => {}
+                    gui::MenuResult::Selected { selected } => match gui::MAIN_MENU[selected] {
+                        MenuAction::NewGame => {
+                            self.new_game();
+                            newrunstate = RunState::PreRun;
+                        }
+                        MenuAction::Continue => newrunstate = RunState::AwaitingInput,
+                        MenuAction::Options => {
+                            newrunstate = RunState::Options {
+                                selection: 0,
+                                paused: false,
+                            }
+                        }
+                        _ => ctx.quit(),
+                    },
+                }
+            }
+            RunState::PauseMenu { selection } => match gui::pause_menu(ctx, selection) {
+                gui::MenuResult::NoSelection { selected } => {
+                    newrunstate = RunState::PauseMenu {
+                        selection: selected,
+                    }
+                }
+                gui::MenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                gui::MenuResult::Selected { selected } => match gui::PAUSE_MENU[selected] {
+                    MenuAction::Resume => newrunstate = RunState::AwaitingInput,
+                    MenuAction::Options => {
+                        newrunstate = RunState::Options {
+                            selection: 0,
+                            paused: true,
+                        }
+                    }
+                    MenuAction::EndGame => {
+                        self.in_progress = false;
+                        newrunstate = RunState::GameOver;
+                    }
+                    _ => newrunstate = RunState::MainMenu { selection: 0 },
+                },
+            },
+            RunState::Options { selection, paused } => {
+                let back = if paused {
+                    RunState::PauseMenu {
+                        selection: gui::menu_position(&gui::PAUSE_MENU, MenuAction::Options),
+                    }
+                } else {
+                    RunState::MainMenu {
+                        selection: gui::menu_position(&gui::MAIN_MENU, MenuAction::Options),
+                    }
+                };
+                match gui::options_menu(&self.ecs, ctx, selection) {
+                    gui::MenuResult::NoSelection { selected } => {
+                        newrunstate = RunState::Options {
+                            selection: selected,
+                            paused,
+                        }
+                    }
+                    gui::MenuResult::Cancel => newrunstate = back,
+                    gui::MenuResult::Selected { selected } => match selected {
+                        0 => {
+                            let mut overlay = self.ecs.write_resource::<DebugOverlay>();
+                            overlay.scent = !overlay.scent;
+                        }
+                        1..=5 => {
+                            let mut settings = self.ecs.write_resource::<Settings>();
+                            match selected {
+                                1 => settings.tileset = settings.tileset.next(),
+                                2 => settings.scanlines = !settings.scanlines,
+                                3 => settings.screen_burn = !settings.screen_burn,
+                                4 => settings.font = settings.font.next(),
+                                _ => settings.reduce_motion = !settings.reduce_motion,
+                            }
+                            ctx.post_scanlines = settings.scanlines;
+                            ctx.post_screenburn = settings.screen_burn;
+                            settings.save();
+                        }
+                        _ => newrunstate = back,
+                    },
+                }
+            }
+            RunState::GameOver => {
+                if gui::game_over(&self.ecs, ctx) {
+                    newrunstate = RunState::MainMenu { selection: 0 };
+                }
+            }
+            RunState::ShowBindings => {
+                if gui::show_bindings(self, ctx) == gui::BindingsScreenResult::Close {
+                    newrunstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::ShowLog => {
+                if gui::show_log(self, ctx) == gui::LogViewerResult::Close {
+                    newrunstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::ShowThrowItem => {
+                let (result, item) = gui::throw_item_menu(self, ctx);
+                match result {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = item.unwrap();
+                        let player_pos = *self.ecs.fetch::<Point>();
+                        let mut cursor = self.ecs.write_resource::<gui::LookCursor>();
+                        cursor.x = player_pos.x;
+                        cursor.y = player_pos.y;
+                        cursor.mouse = ctx.mouse_pos();
+                        let throwables = self.ecs.read_storage::<Throwable>();
+                        newrunstate = RunState::ShowTargeting {
+                            range: throwables.get(item).unwrap().range,
+                            item,
+                        };
+                    }
+                }
+            }
+            RunState::ShowTargeting { range, item } => {
+                let (result, target) = gui::ranged_target(self, ctx, range);
+                match result {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let player_entity = self.ecs.fetch::<Entity>();
+                        let mut intent = self.ecs.write_storage::<WantsToThrow>();
+                        intent
+                            .insert(
+                                *player_entity,
+                                WantsToThrow {
+                                    item,
+                                    target: target.unwrap(),
+                                },
+                            )
+                            .expect("Unable to insert intent");
+                        newrunstate = RunState::PlayerTurn;
+                    }
+                }
             }
         }
 
@@ -77,31 +458,34 @@ impl GameState for State {
             let mut runwriter = self.ecs.write_resource::<RunState>();
             *runwriter = newrunstate;
         }
-
-        draw_map(&self.ecs, ctx);
-
-        let positions = self.ecs.read_storage::<Position>();
-        let renderables = self.ecs.read_storage::<Renderable>();
-        let map = self.ecs.fetch::<Map>();
-
-        let mut data = (&positions, &renderables).join().collect::<Vec<_>>();
-        data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
-
-        for (pos, render) in data.iter() {
-            let idx = map.xy_idx(pos.x, pos.y);
-            if map.visible_tiles[idx] {
-                ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph)
-            }
-        }
-
-        gui::draw_ui(&self.ecs, ctx);
     }
 }
 
 fn main() -> rltk::BError {
     use rltk::RltkBuilder;
-    let context = RltkBuilder::simple80x50().with_title("moonshot").build()?;
-    let mut gs = State { ecs: World::new() };
+    let settings = settings::load_settings();
+    let (font_width, font_height) = settings.font.size();
+    tileset::link_tile_sheet();
+    let mut context = RltkBuilder::new()
+        .with_dimensions(80, 50)
+        .with_tile_dimensions(font_width, font_height)
+        .with_title("moonshot")
+        .with_font(settings.font.file(), font_width, font_height)
+        .with_font(tileset::TILE_SHEET, 16, 16)
+        .with_simple_console(80, 50, settings.font.file())
+        .with_sparse_console(80, 50, tileset::TILE_SHEET)
+        .with_sparse_console(80, 50, settings.font.file())
+        .with_sparse_console(80, 50, settings.font.file())
+        .build()?;
+    context.post_scanlines = settings.scanlines;
+    context.post_screenburn = settings.screen_burn;
+    let mut gs = State {
+        ecs: World::new(),
+        in_progress: false,
+    };
+
+    raws::load_raws()?;
+    let bindings = keybindings::load_bindings()?;
 
     gs.ecs.register::<Position>();
     gs.ecs.register::<Renderable>();
@@ -109,27 +493,36 @@ fn main() -> rltk::BError {
     gs.ecs.register::<Moonshot>();
     gs.ecs.register::<Viewshed>();
     gs.ecs.register::<Name>();
+    gs.ecs.register::<Description>();
     gs.ecs.register::<BlocksVisibility>();
     gs.ecs.register::<Door>();
     gs.ecs.register::<BlocksTile>();
-
-    let map: Map = Map::test_map();
-    let (player_x, player_y) = (35, 26);
+    gs.ecs.register::<Hearing>();
+    gs.ecs.register::<Investigate>();
+    gs.ecs.register::<LeavesScent>();
+    gs.ecs.register::<Wildlife>();
+    gs.ecs.register::<Item>();
+    gs.ecs.register::<InBackpack>();
+    gs.ecs.register::<WantsToPickup>();
+    gs.ecs.register::<WantsToDrop>();
+    gs.ecs.register::<Throwable>();
+    gs.ecs.register::<WantsToThrow>();
+    gs.ecs.register::<Projectile>();
+    gs.ecs.register::<Fetching>();
+    gs.ecs.register::<ParticleLifetime>();
+    gs.ecs.register::<Stamina>();
+    gs.ecs.register::<Wet>();
+    gs.ecs.register::<MoveDelay>();
 
     gs.ecs.insert(rltk::RandomNumberGenerator::new());
-    gs.ecs.insert(RunState::PreRun);
-    gs.ecs.insert(map);
-    gs.ecs.insert(Point::new(player_x, player_y));
-
-    // Spawn entities
-    spawners::door(&mut gs.ecs, 38, 29);
-    spawners::player(&mut gs.ecs, player_x, player_y);
-    spawners::moonshot(&mut gs.ecs, 37, 30);
-
-    // Add Gamelog
-    gs.ecs.insert(gamelog::GameLog {
-        entries: vec!["You wake to the sound of scratching coming from the door".to_string()],
-    });
+    gs.ecs.insert(RunState::MainMenu { selection: 0 });
+    gs.ecs.insert(DebugOverlay::default());
+    gs.ecs.insert(TerrainAnimation::default());
+    gs.ecs.insert(Minimap::default());
+    gs.ecs.insert(settings);
+    gs.ecs.insert(gui::LogViewer::default());
+    gs.ecs.insert(gui::LookCursor::default());
+    gs.ecs.insert(bindings);
 
     rltk::main_loop(context, gs)
 }
@@ -1,12 +1,13 @@
-use super::{Door, Position, Rect, Renderable};
+use super::{camera, layers, tileset, Rect, Settings, Tileset, VIEW_HEIGHT, VIEW_WIDTH};
 use rltk::{Algorithm2D, BaseMap, Point, RandomNumberGenerator, Rltk, SmallVec, RGB};
 use specs::prelude::*;
 use std::cmp::{max, min};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 const MAPWIDTH: usize = 80;
 const MAPHEIGHT: usize = 44;
 const MAPCOUNT: usize = MAPHEIGHT * MAPWIDTH;
+pub const MAX_SCENT: i32 = 100;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum TileType {
@@ -34,23 +35,76 @@ impl TileType {
       '▲' => TileType::Mountain,
       ' ' => TileType::Empty,
       '~' => TileType::ShallowWater,
-      '~' => TileType::DeepWater,
+      '≈' => TileType::DeepWater,
       _ => TileType::Empty,
     }
   }
 
+  pub fn name(&self) -> &'static str {
+    match &self {
+      TileType::Empty => "Nothing",
+      TileType::WallV => "Wall",
+      TileType::WallH => "Wall",
+      TileType::Floor => "Floor",
+      TileType::Door => "Doorway",
+      TileType::Tree => "Tree",
+      TileType::TallGrass => "Tall grass",
+      TileType::Mountain => "Mountain",
+      TileType::ShallowWater => "Shallow water",
+      TileType::DeepWater => "Deep water",
+    }
+  }
+
+  /// Where the tile appears in the tile sheet, if it has a sprite there.
+  pub fn sprite(&self) -> Option<u16> {
+    match &self {
+      TileType::WallV | TileType::WallH => Some(0),
+      TileType::Floor => Some(1),
+      _ => None,
+    }
+  }
+
   pub fn is_blocked(&self) -> bool {
     match &self {
       TileType::Tree => true,
       TileType::WallV => true,
       TileType::WallH => true,
       TileType::Mountain => true,
-      TileType::DeepWater => true,
       TileType::Door => false,
       TileType::TallGrass => false,
       TileType::Floor => false,
       TileType::Empty => false,
       TileType::ShallowWater => false,
+      TileType::DeepWater => false,
+    }
+  }
+
+  /// How much harder than open ground the tile is to cross, for path finding.
+  pub fn move_cost(&self) -> f32 {
+    match &self {
+      TileType::ShallowWater => 2.0,
+      TileType::DeepWater => 4.0,
+      _ => 1.0,
+    }
+  }
+
+  pub fn is_water(&self) -> bool {
+    matches!(self, TileType::ShallowWater | TileType::DeepWater)
+  }
+
+  /// How much loudness a sound loses when it travels into this tile.
+  pub fn sound_dampening(&self) -> i32 {
+    match &self {
+      TileType::WallV => 6,
+      TileType::WallH => 6,
+      TileType::Mountain => 10,
+      TileType::Tree => 2,
+      TileType::TallGrass => 2,
+      TileType::Door => 1,
+      TileType::Floor => 1,
+      TileType::Empty => 1,
+      TileType::ShallowWater => 1,
+      TileType::DeepWater => 1,
     }
   }
 }
@@ -58,6 +112,7 @@ impl TileType {
 #[derive(Default)]
 pub struct Map {
   pub tiles: Vec<TileType>,
+  /// Buildings, whose insides are sheltered from the weather.
   pub rooms: Vec<Rect>,
   pub width: i32,
   pub height: i32,
@@ -66,6 +121,57 @@ pub struct Map {
   pub blocked: Vec<bool>,
   pub view_blocked: HashSet<usize>,
   pub tile_content: Vec<Vec<Entity>>,
+  pub scent: Vec<i32>,
+}
+
+/// Debug layers that can be drawn on top of the map.
+#[derive(Default)]
+pub struct DebugOverlay {
+  pub scent: bool,
+}
+
+/// Keeps time for the moving parts of the map, and which patches of grass are being brushed
+/// past.
+#[derive(Default)]
+pub struct TerrainAnimation {
+  pub elapsed_ms: f32,
+  rustling: HashMap<usize, f32>,
+}
+
+/// How long grass keeps swaying after something pushes through it.
+const RUSTLE_MS: f32 = 800.0;
+
+impl TerrainAnimation {
+  pub fn advance(&mut self, frame_time_ms: f32) {
+    self.elapsed_ms += frame_time_ms;
+    self.rustling.retain(|_, left| {
+      *left -= frame_time_ms;
+      *left > 0.0
+    });
+  }
+
+  pub fn rustle(&mut self, idx: usize) {
+    self.rustling.insert(idx, RUSTLE_MS);
+  }
+}
+
+/// A fixed pseudo-random number for a tile, so neighbouring tiles of the same kind can differ
+/// without the difference changing from one frame to the next.
+pub fn tile_variation(idx: usize) -> u32 {
+  let mut x = (idx as u32) ^ 0x9e37_79b9;
+  x = x.wrapping_mul(0x85eb_ca6b);
+  x ^= x >> 13;
+  x = x.wrapping_mul(0xc2b2_ae35);
+  x ^ (x >> 16)
+}
+
+/// Everything besides the map itself that changes how a tile is drawn.
+struct TileStyle<'a> {
+  overlay: &'a DebugOverlay,
+  tileset: Tileset,
+  animation: &'a TerrainAnimation,
+  /// False when the player has asked for the map to hold still.
+  animate: bool,
 }
 
 impl Map {
@@ -81,6 +187,34 @@ impl Map {
     !self.blocked[idx]
   }
 
+  /// Returns true if any of the tiles surrounding x, y are of the given type.
+  pub fn is_near(&self, x: i32, y: i32, tile: TileType) -> bool {
+    for dy in -1..=1 {
+      for dx in -1..=1 {
+        let (nx, ny) = (x + dx, y + dy);
+        let in_bounds = nx >= 0 && nx < self.width && ny >= 0 && ny < self.height;
+        if in_bounds && self.tiles[self.xy_idx(nx, ny)] == tile {
+          return true;
+        }
+      }
+    }
+    false
+  }
+
+  /// Returns true if x, y is inside one of the buildings, out of the weather.
+  pub fn is_indoors(&self, x: i32, y: i32) -> bool {
+    self.rooms.iter().any(|room| room.contains(x, y))
+  }
+
+  /// Loudness lost entering a tile, counting closed doors and other view blockers.
+  pub fn sound_cost(&self, idx: usize) -> i32 {
+    let mut cost = self.tiles[idx].sound_dampening();
+    if self.view_blocked.contains(&idx) {
+      cost += 3;
+    }
+    cost
+  }
+
   pub fn populate_blocked(&mut self) {
     for (i, tile) in self.tiles.iter_mut().enumerate() {
       self.blocked[i] = tile.is_blocked()
@@ -93,6 +227,7 @@ impl Map {
     }
   }
 
+  #[allow(dead_code)]
   fn apply_room_to_map(&mut self, room: &Rect) {
     for y in room.y1 + 1..=room.y2 {
       for x in room.x1 + 1..=room.x2 {
@@ -102,20 +237,22 @@ impl Map {
     }
   }
 
+  #[allow(dead_code)]
   fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
     for x in min(x1, x2)..=max(x1, x2) {
       let idx = self.xy_idx(x, y);
       if idx > 0 && idx < self.width as usize * self.height as usize {
-        self.tiles[idx as usize] = TileType::Floor;
+        self.tiles[idx] = TileType::Floor;
       }
     }
   }
 
+  #[allow(dead_code)]
   fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
     for y in min(y1, y2)..=max(y1, y2) {
       let idx = self.xy_idx(x, y);
       if idx > 0 && idx < self.width as usize * self.height as usize {
-        self.tiles[idx as usize] = TileType::Floor;
+        self.tiles[idx] = TileType::Floor;
       }
     }
   }
@@ -131,6 +268,7 @@ impl Map {
       blocked: vec![false; MAPCOUNT],
       view_blocked: HashSet::new(),
       tile_content: vec![Vec::new(); MAPCOUNT],
+      scent: vec![0; MAPCOUNT],
     };
 
     let tiles = vec![
@@ -175,9 +313,9 @@ impl Map {
       "...........................~~~~~~~~~~~~~~~~~~~~~~~~~~~~.........................",
       "...............~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~.................",
       "..........~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~.......",
-      "...~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~..",
-      "~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~",
-      "~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~",
+      "...~~~~~~~~~~~~~~~~~~~~~~~~~≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~..",
+      "~~~~~~~~~~~~~~~≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈~~~~~~~~~~~~~~~~~~~~~~",
+      "~~~~~~~~~≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈≈~~~~~~~~~~~~~~~~",
     ];
 
     for row in tiles.iter() {
@@ -187,12 +325,15 @@ impl Map {
         map.tiles.push(TileType::tile_from_glyph(glyph));
       }
     }
+    // The hut, walls included
+    map.rooms.push(Rect::new(34, 24, 8, 5));
 
     map
   }
 
   /// Makes a new map using the algorithm from http://rogueliketutorials.com/tutorials/tcod/part-3/
   /// This gives a handful of random rooms and corridors joining them together.
+  #[allow(dead_code)]
   pub fn new_map_rooms_and_corridors() -> Map {
     let mut map = Map {
       tiles: vec![TileType::Tree; MAPCOUNT],
@@ -204,6 +345,7 @@ impl Map {
       blocked: vec![false; MAPCOUNT],
       view_blocked: HashSet::new(),
       tile_content: vec![Vec::new(); MAPCOUNT],
+      scent: vec![0; MAPCOUNT],
     };
 
     const MAX_ROOMS: i32 = 30;
@@ -249,12 +391,10 @@ impl Map {
 
 impl BaseMap for Map {
   fn is_opaque(&self, idx: usize) -> bool {
-    let is_match = match self.tiles[idx] {
-      TileType::WallV => true,
-      TileType::WallH => true,
-      TileType::Door => true,
-      _ => false,
-    };
+    let is_match = matches!(
+      self.tiles[idx],
+      TileType::WallV | TileType::WallH | TileType::Door
+    );
 
     is_match || self.view_blocked.contains(&idx)
   }
@@ -300,6 +440,10 @@ impl BaseMap for Map {
       exits.push(((idx + w) + 1, 1.45));
     }
 
+    // Water is slow going, so paths go round it where they can
+    for exit in exits.iter_mut() {
+      exit.1 *= self.tiles[exit.0].move_cost();
+    }
     exits
   }
 }
@@ -311,82 +455,113 @@ impl Algorithm2D for Map {
 }
 
 pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
+  let origin = camera::origin(ecs);
   let map = ecs.fetch::<Map>();
+  let overlay = ecs.fetch::<DebugOverlay>();
+  let settings = ecs.fetch::<Settings>();
+  let animation = ecs.fetch::<TerrainAnimation>();
+  let style = TileStyle {
+    overlay: &overlay,
+    tileset: settings.tileset,
+    animation: &animation,
+    animate: !settings.reduce_motion,
+  };
+
+  for (y, ty) in (origin.y..(origin.y + VIEW_HEIGHT).min(map.height)).enumerate() {
+    for (x, tx) in (origin.x..(origin.x + VIEW_WIDTH).min(map.width)).enumerate() {
+      draw_tile(&map, &style, ctx, map.xy_idx(tx, ty), x as i32, y as i32);
+    }
+  }
+}
 
-  let mut y = 0;
-  let mut x = 0;
-  for (idx, tile) in map.tiles.iter().enumerate() {
+/// How bright a water tile is, from 0 to 1. Each tile starts its cycle at a different point, so
+/// the light moves across the water rather than the whole lake pulsing at once.
+fn water_shimmer(style: &TileStyle, variation: u32, moving: bool) -> f32 {
+  let offset = (variation % 628) as f32 / 100.0;
+  let phase = if moving {
+    style.animation.elapsed_ms / 600.0 + offset
+  } else {
+    offset
+  };
+  (phase.sin() + 1.0) / 2.0
+}
+
+fn draw_tile(map: &Map, style: &TileStyle, ctx: &mut Rltk, idx: usize, x: i32, y: i32) {
+  let tile = &map.tiles[idx];
+  let sprite = style.tileset.sprite(tile.sprite());
+  let variation = tile_variation(idx);
+  let moving = style.animate && map.visible_tiles[idx];
+  if let (true, Some(sprite)) = (map.revealed_tiles[idx], sprite) {
+    let bg = RGB::named(rltk::BLACK).into();
+    tileset::draw_sprite(ctx, x, y, sprite, map.visible_tiles[idx], bg);
+  } else if map.revealed_tiles[idx] {
     // Render a tile depending upon the tile type
-    if map.revealed_tiles[idx] {
-      let glyph;
-      let mut fg;
-      match tile {
-        TileType::Empty => {
-          glyph = rltk::to_cp437(' ');
-          fg = RGB::from_f32(0.0, 0.0, 0.0);
-        }
-        TileType::Floor => {
-          glyph = rltk::to_cp437('.');
-          fg = RGB::from_f32(0.0, 0.5, 0.5);
-        }
-        TileType::WallV => {
-          glyph = rltk::to_cp437('|');
-          fg = RGB::from_f32(0., 1.0, 0.);
-        }
-        TileType::WallH => {
-          glyph = rltk::to_cp437('-');
-          fg = RGB::from_f32(0., 1.0, 0.);
-        }
-        TileType::Door => {
-          glyph = rltk::to_cp437('+');
-          fg = RGB::from_f32(0., 1.0, 0.);
-        }
-        TileType::Tree => {
-          glyph = rltk::to_cp437('#');
-          fg = RGB::from_f32(0., 1.0, 0.);
-        }
-        TileType::TallGrass => {
-          glyph = rltk::to_cp437('\'');
-          fg = RGB::from_f32(0., 0.5, 0.);
-        }
-        TileType::Mountain => {
-          glyph = rltk::to_cp437('▲');
-          fg = RGB::from_f32(0.0, 0.0, 1.0);
-        }
-        TileType::ShallowWater => {
-          glyph = rltk::to_cp437('~');
-          fg = RGB::from_f32(0.0, 0.0, 0.7);
-        }
-        TileType::DeepWater => {
-          glyph = rltk::to_cp437('~');
-          fg = RGB::from_f32(0.0, 0.0, 0.9);
-        }
+    let glyph;
+    let mut fg;
+    match tile {
+      TileType::Empty => {
+        glyph = rltk::to_cp437(' ');
+        fg = RGB::from_f32(0.0, 0.0, 0.0);
+      }
+      TileType::Floor => {
+        glyph = rltk::to_cp437('.');
+        fg = RGB::from_f32(0.0, 0.5, 0.5);
+      }
+      TileType::WallV => {
+        glyph = rltk::to_cp437('|');
+        fg = RGB::from_f32(0., 1.0, 0.);
+      }
+      TileType::WallH => {
+        glyph = rltk::to_cp437('-');
+        fg = RGB::from_f32(0., 1.0, 0.);
       }
-      if !map.visible_tiles[idx] {
-        fg = fg.to_greyscale()
+      TileType::Door => {
+        glyph = rltk::to_cp437('+');
+        fg = RGB::from_f32(0., 1.0, 0.);
+      }
+      TileType::Tree => {
+        glyph = rltk::to_cp437('#');
+        fg = RGB::from_f32(0., 1.0, 0.);
+      }
+      TileType::TallGrass => {
+        // Blades lean this way and that, and bend back and forth while something pushes past
+        const BLADES: [char; 4] = ['\'', '"', '`', ','];
+        let shade = 0.4 + (variation % 5) as f32 * 0.05;
+        glyph = match style.animation.rustling.get(&idx) {
+          Some(_) if moving => {
+            let sway = ((style.animation.elapsed_ms / 150.0) as u32).wrapping_add(variation);
+            rltk::to_cp437(['/', '\\'][sway as usize % 2])
+          }
+          _ => rltk::to_cp437(BLADES[variation as usize % BLADES.len()]),
+        };
+        fg = RGB::from_f32(0., shade, 0.);
+      }
+      TileType::Mountain => {
+        glyph = rltk::to_cp437('▲');
+        fg = RGB::from_f32(0.0, 0.0, 1.0);
+      }
+      TileType::ShallowWater => {
+        glyph = rltk::to_cp437('~');
+        let shimmer = water_shimmer(style, variation, moving);
+        fg = RGB::from_f32(0.0, 0.15 + shimmer * 0.15, 0.6 + shimmer * 0.2);
+      }
+      TileType::DeepWater => {
+        glyph = rltk::to_cp437('~');
+        let shimmer = water_shimmer(style, variation, moving);
+        fg = RGB::from_f32(0.0, shimmer * 0.1, 0.8 + shimmer * 0.2);
       }
-      ctx.set(x, y, fg, RGB::from_f32(0., 0., 0.), glyph);
     }
-
-    // let doors = ecs.write_storage::<Door>();
-    // let positions = ecs.write_storage::<Position>();
-    // let mut renderables = ecs.write_storage::<Renderable>();
-
-    // for (pos, _door, renderable) in (&positions, &doors, &mut renderables).join() {
-    //   if idx == map.xy_idx(pos.x, pos.y) {
-    //     if map.revealed_tiles[idx] {
-    //       renderable.fg = RGB::named(rltk::CHOCOLATE)
-    //     } else if map.visible_tiles[idx] {
-    //       renderable.fg = renderable.fg.to_greyscale()
-    //     }
-    //   }
-    // }
-
-    // Move the coordinates
-    x += 1;
-    if x > MAPWIDTH - 1 {
-      x = 0;
-      y += 1;
+    if !map.visible_tiles[idx] {
+      fg = fg.to_greyscale()
     }
+    ctx.set(x, y, fg, RGB::from_f32(0., 0., 0.), glyph);
+  }
+
+  if style.overlay.scent && map.scent[idx] > 0 {
+    let strength = map.scent[idx] as f32 / MAX_SCENT as f32;
+    let colour = RGB::from_f32(strength, strength * 0.5, 0.);
+    layers::draw_on(ctx, layers::UI, |ctx| {
+      ctx.set_bg(x, y, layers::highlight(colour))
+    });
   }
 }
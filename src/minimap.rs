@@ -0,0 +1,123 @@
+use super::{camera, Door, Map, Moonshot, Player, Position, Renderable, TileType};
+use rltk::{Rltk, RGB};
+use specs::prelude::*;
+
+/// Largest the minimap may be, in cells, not counting its border.
+const MAX_WIDTH: i32 = 28;
+const MAX_HEIGHT: i32 = 14;
+
+/// Whether the minimap is drawn over the corner of the view.
+#[derive(Default)]
+pub struct Minimap {
+  pub shown: bool,
+}
+
+/// How a tile looks on the minimap. When several tiles share a cell the one with the highest
+/// rank wins, so doors and walls are not lost among the floor around them.
+fn terrain(tile: TileType) -> (i32, RGB) {
+  match tile {
+    TileType::Empty => (0, RGB::named(rltk::BLACK)),
+    TileType::Floor => (1, RGB::from_f32(0.0, 0.35, 0.35)),
+    TileType::TallGrass => (2, RGB::from_f32(0.0, 0.4, 0.0)),
+    TileType::Tree => (3, RGB::from_f32(0.0, 0.7, 0.0)),
+    TileType::ShallowWater => (4, RGB::from_f32(0.2, 0.3, 0.8)),
+    TileType::DeepWater => (5, RGB::from_f32(0.0, 0.0, 0.7)),
+    TileType::Mountain => (6, RGB::from_f32(0.5, 0.5, 0.6)),
+    TileType::WallV | TileType::WallH => (7, RGB::from_f32(0.8, 0.8, 0.8)),
+    TileType::Door => (8, RGB::named(rltk::BROWN1)),
+  }
+}
+
+/// Draws the revealed part of the map, shrunk so the whole of it fits in a small box in the top
+/// right corner of the view.
+pub fn draw_minimap(ecs: &World, ctx: &mut Rltk) {
+  if !ecs.fetch::<Minimap>().shown {
+    return;
+  }
+  let map = ecs.fetch::<Map>();
+  let origin = camera::origin(ecs);
+  let doors = ecs.read_storage::<Door>();
+  let moonshots = ecs.read_storage::<Moonshot>();
+  let players = ecs.read_storage::<Player>();
+  let positions = ecs.read_storage::<Position>();
+  let renderables = ecs.read_storage::<Renderable>();
+
+  // Every cell stands for a square of tiles, big enough that the map fits both ways
+  let scale = ((map.width + MAX_WIDTH - 1) / MAX_WIDTH)
+    .max((map.height + MAX_HEIGHT - 1) / MAX_HEIGHT)
+    .max(1);
+  let width = (map.width + scale - 1) / scale;
+  let height = (map.height + scale - 1) / scale;
+  let left = camera::VIEW_WIDTH - width - 3;
+  let top = 1;
+  let black = RGB::named(rltk::BLACK);
+  ctx.draw_box(
+    left,
+    top,
+    width + 1,
+    height + 1,
+    RGB::named(rltk::WHITE),
+    black,
+  );
+  ctx.print_color(left + 2, top, RGB::named(rltk::YELLOW), black, "Map");
+
+  for cy in 0..height {
+    for cx in 0..width {
+      let mut best: Option<(i32, RGB)> = None;
+      for y in cy * scale..((cy + 1) * scale).min(map.height) {
+        for x in cx * scale..((cx + 1) * scale).min(map.width) {
+          let idx = map.xy_idx(x, y);
+          if !map.revealed_tiles[idx] {
+            continue;
+          }
+          let here = terrain(map.tiles[idx]);
+          if best.is_none_or(|(rank, _)| here.0 > rank) {
+            best = Some(here);
+          }
+        }
+      }
+
+      // Shade the part of the map the view is showing
+      let (x, y) = (cx * scale, cy * scale);
+      let in_view = x + scale > origin.x
+        && x < origin.x + camera::VIEW_WIDTH
+        && y + scale > origin.y
+        && y < origin.y + camera::VIEW_HEIGHT;
+      let bg = if in_view {
+        RGB::from_f32(0.1, 0.1, 0.15)
+      } else {
+        black
+      };
+      match best {
+        Some((_, colour)) => ctx.set(left + 1 + cx, top + 1 + cy, colour, bg, rltk::to_cp437('█')),
+        None => ctx.set(left + 1 + cx, top + 1 + cy, bg, bg, rltk::to_cp437(' ')),
+      }
+    }
+  }
+
+  // Known doors first, so Moonshot and the player are drawn over them
+  let mut marked: Vec<(&Position, &Renderable)> = (&doors, &positions, &renderables)
+    .join()
+    .filter(|(_, pos, _)| map.revealed_tiles[map.xy_idx(pos.x, pos.y)])
+    .map(|(_, pos, render)| (pos, render))
+    .collect();
+  marked.extend(
+    (&moonshots, &positions, &renderables)
+      .join()
+      .map(|(_, p, r)| (p, r)),
+  );
+  marked.extend(
+    (&players, &positions, &renderables)
+      .join()
+      .map(|(_, p, r)| (p, r)),
+  );
+  for (pos, render) in marked {
+    ctx.set(
+      left + 1 + pos.x / scale,
+      top + 1 + pos.y / scale,
+      render.fg,
+      black,
+      render.glyph,
+    );
+  }
+}
@@ -1,75 +1,287 @@
-use super::{Map, Moonshot, Name, Position, RunState, Viewshed};
-use rltk::{console, field_of_view, Point};
+use super::{
+  CompanionActivity, Fetching, GameEvent, GameEvents, InBackpack, Investigate, Map, Moonshot,
+  MoveDelay, NoiseQueue, Position, RunState, Stamina, TileType, Viewshed, WantsToDrop,
+  WantsToPickup,
+};
+use crate::water_system::nearest_shore;
+use rltk::{Point, RandomNumberGenerator};
 use specs::prelude::*;
 
+/// Actions a companion can have in its repertoire.
+pub const COMPANION_ACTIONS: &[&str] = &["follow_player", "wait", "explore_nearby"];
+
 pub struct MoonshotAI {}
 
+/// Finds the neighbouring tile with a fresher scent than the one Moonshot is standing on.
+fn sniff(map: &Map, pos: &Position) -> Option<Point> {
+  let mut best = map.scent[map.xy_idx(pos.x, pos.y)];
+  let mut target = None;
+  for dy in -1..=1 {
+    for dx in -1..=1 {
+      let (x, y) = (pos.x + dx, pos.y + dy);
+      if x < 0 || x >= map.width || y < 0 || y >= map.height {
+        continue;
+      }
+      let idx = map.xy_idx(x, y);
+      if !map.blocked[idx] && map.scent[idx] > best {
+        best = map.scent[idx];
+        target = Some(Point::new(x, y));
+      }
+    }
+  }
+  target
+}
+
+/// Takes a single step along the A* path towards the target, returning false if there is none.
+fn step_towards(map: &Map, pos: &mut Position, x: i32, y: i32) -> bool {
+  let path = rltk::a_star_search(
+    map.xy_idx(pos.x, pos.y) as i32,
+    map.xy_idx(x, y) as i32,
+    map,
+  );
+  if path.success && path.steps.len() > 1 {
+    pos.x = path.steps[1] as i32 % map.width;
+    pos.y = path.steps[1] as i32 / map.width;
+    return true;
+  }
+  false
+}
+
+/// Swims to a random neighbouring tile of deep water, returning false if there is none.
+fn paddle(map: &Map, pos: &mut Position, rng: &mut RandomNumberGenerator) -> bool {
+  let mut options: Vec<Point> = Vec::new();
+  for dy in -1..=1 {
+    for dx in -1..=1 {
+      let (x, y) = (pos.x + dx, pos.y + dy);
+      if (dx == 0 && dy == 0) || x < 0 || x >= map.width || y < 0 || y >= map.height {
+        continue;
+      }
+      let idx = map.xy_idx(x, y);
+      if map.tiles[idx] == TileType::DeepWater && !map.blocked[idx] {
+        options.push(Point::new(x, y));
+      }
+    }
+  }
+  if options.is_empty() {
+    return false;
+  }
+  let choice = options[(rng.roll_dice(1, options.len() as i32) - 1) as usize];
+  pos.x = choice.x;
+  pos.y = choice.y;
+  true
+}
+
 impl<'a> System<'a> for MoonshotAI {
   type SystemData = (
-    WriteExpect<'a, Map>,
+    ReadExpect<'a, Map>,
     ReadExpect<'a, Point>,
     ReadExpect<'a, RunState>,
     WriteStorage<'a, Viewshed>,
     WriteStorage<'a, Position>,
-    ReadStorage<'a, Name>,
-    ReadStorage<'a, Moonshot>,
+    WriteStorage<'a, Moonshot>,
     WriteExpect<'a, rltk::RandomNumberGenerator>,
+    Entities<'a>,
+    WriteStorage<'a, Investigate>,
+    WriteExpect<'a, NoiseQueue>,
+    WriteStorage<'a, Fetching>,
+    ReadStorage<'a, InBackpack>,
+    WriteStorage<'a, WantsToPickup>,
+    WriteStorage<'a, WantsToDrop>,
+    WriteExpect<'a, GameEvents>,
+    WriteStorage<'a, MoveDelay>,
+    ReadStorage<'a, Stamina>,
   );
 
   fn run(&mut self, data: Self::SystemData) {
-    let (mut map, player_pos, runstate, mut viewshed, mut pos, name, moonshot, mut rng) = data;
+    let (
+      map,
+      player_pos,
+      runstate,
+      mut viewshed,
+      mut pos,
+      mut moonshot,
+      mut rng,
+      entities,
+      mut investigate,
+      mut noise,
+      mut fetching,
+      backpack,
+      mut wants_pickup,
+      mut wants_drop,
+      mut events,
+      mut delays,
+      stamina,
+    ) = data;
 
     if *runstate != RunState::NpcTurn {
       return;
     }
 
-    for (mut viewshed, mut pos, moonshot, name) in
-      (&mut viewshed, &mut pos, &moonshot, &name).join()
+    // Where each fetch target is: on the ground, or carried by someone
+    let fetches: Vec<(Entity, Entity, Option<Point>, Option<Entity>)> = (&entities, &fetching)
+      .join()
+      .map(|(dog, fetch)| {
+        (
+          dog,
+          fetch.item,
+          pos.get(fetch.item).map(|p| Point::new(p.x, p.y)),
+          backpack.get(fetch.item).map(|b| b.owner),
+        )
+      })
+      .collect();
+
+    for (entity, viewshed, pos, moonshot) in
+      (&entities, &mut viewshed, &mut pos, &mut moonshot).join()
     {
-      let action_roll = rng.roll_dice(1, moonshot.actions.len() as i32);
-      let action = &moonshot.actions[(action_roll - 1) as usize];
-
-      match action.as_ref() {
-        "follow_player" => {
-          let distance =
-            rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-
-          if distance < 2.0 {
-            console::log(format!("{} stops to rest", name.name.to_string()));
-          } else if viewshed.visible_tiles.contains(&*player_pos) {
-            console::log(format!("{} follows you", name.name.to_string()));
-            let mut x = player_pos.x;
-            let mut y = player_pos.y;
-            let move_roll = rng.roll_dice(1, 5);
-            match move_roll {
-              1 => x -= 2,
-              2 => x += 2,
-              3 => y -= 2,
-              4 => y += 2,
-              _ => {}
-            }
-            let path = rltk::a_star_search(
-              map.xy_idx(pos.x, pos.y) as i32,
-              map.xy_idx(x, y) as i32,
-              &mut *map,
-            );
-            if path.success && path.steps.len() > 1 {
-              pos.x = path.steps[1] as i32 % map.width;
-              pos.y = path.steps[1] as i32 / map.width;
-              viewshed.dirty = true;
+      let here = Point::new(pos.x, pos.y);
+      let can_see_player = viewshed.visible_tiles.contains(&*player_pos);
+      if can_see_player && !moonshot.saw_player {
+        events.emit(GameEvent::NoticedPlayer { entity });
+      }
+      moonshot.saw_player = can_see_player;
+
+      // Still wading through the last step
+      if let Some(delay) = delays.get_mut(entity) {
+        delay.turns -= 1;
+        if delay.turns <= 0 {
+          delays.remove(entity);
+        }
+        continue;
+      }
+
+      let distance = rltk::DistanceAlg::Pythagoras.distance2d(here, *player_pos);
+      let mut activity = None;
+      let energy = stamina.get(entity);
+
+      // Worn out from swimming, so back to the shore before anything else
+      let in_deep_water = map.tiles[map.xy_idx(pos.x, pos.y)] == TileType::DeepWater;
+      if in_deep_water && energy.is_some_and(|s| s.current == 0) {
+        let shore = nearest_shore(&map, here);
+        step_towards(&map, pos, shore.x, shore.y);
+        activity = Some(CompanionActivity::Tired);
+      }
+
+      // Chasing something the player threw, then bringing it back
+      if let (None, Some((_, item, item_pos, carrier))) =
+        (activity, fetches.iter().find(|f| f.0 == entity))
+      {
+        investigate.remove(entity);
+        match (item_pos, carrier) {
+          (Some(at), _) if *at == here => {
+            wants_pickup
+              .insert(
+                entity,
+                WantsToPickup {
+                  collected_by: entity,
+                  item: *item,
+                },
+              )
+              .expect("Unable to insert want to pickup");
+            activity = Some(CompanionActivity::Chasing);
+          }
+          (Some(at), _) => {
+            activity = step_towards(&map, pos, at.x, at.y).then_some(CompanionActivity::Chasing);
+          }
+          (None, Some(owner)) if *owner == entity => {
+            if distance < 2.0 {
+              wants_drop
+                .insert(entity, WantsToDrop { item: *item })
+                .expect("Unable to insert want to drop");
+              fetching.remove(entity);
+              activity = Some(CompanionActivity::Resting);
+            } else if step_towards(&map, pos, player_pos.x, player_pos.y) {
+              activity = Some(CompanionActivity::Returning);
             }
           }
-          break;
+          _ => {}
         }
-        "wait" => {
-          break;
+        if activity.is_none() {
+          // Out of reach, or somebody else has it now
+          fetching.remove(entity);
+          activity = Some(CompanionActivity::Idle);
         }
-        "explore_nearby" => {
-          break;
+      }
+
+      // Something was heard out of sight, go and find out what it was
+      if let (None, Some(target)) = (activity, investigate.get(entity)) {
+        let (x, y) = (target.x, target.y);
+        if can_see_player || !step_towards(&map, pos, x, y) {
+          investigate.remove(entity);
+        } else {
+          activity = Some(CompanionActivity::Investigating);
         }
-        _ => {
-          break;
+      }
+
+      // Moonshot loves the water, and is in no hurry to come back out of it until tired. They
+      // only jump back in once they have their breath back.
+      if activity.is_none() {
+        let swimming = map.tiles[map.xy_idx(pos.x, pos.y)] == TileType::DeepWater;
+        if swimming {
+          if rng.roll_dice(1, 3) < 3 {
+            paddle(&map, pos, &mut rng);
+          } else {
+            let shore = nearest_shore(&map, here);
+            step_towards(&map, pos, shore.x, shore.y);
+          }
+          activity = Some(CompanionActivity::Swimming);
+        } else if energy.is_none_or(|s| s.current == s.max)
+          && rng.roll_dice(1, 8) == 1
+          && paddle(&map, pos, &mut rng)
+        {
+          activity = Some(CompanionActivity::Swimming);
+        }
+      }
+
+      let activity = match activity {
+        Some(activity) => activity,
+        None => {
+          let action_roll = rng.roll_dice(1, moonshot.actions.len() as i32);
+          match moonshot.actions[(action_roll - 1) as usize].as_ref() {
+            "follow_player" => {
+              if distance < 2.0 {
+                CompanionActivity::Resting
+              } else if can_see_player {
+                let mut x = player_pos.x;
+                let mut y = player_pos.y;
+                let move_roll = rng.roll_dice(1, 5);
+                match move_roll {
+                  1 => x -= 2,
+                  2 => x += 2,
+                  3 => y -= 2,
+                  4 => y += 2,
+                  _ => {}
+                }
+                step_towards(&map, pos, x, y);
+                CompanionActivity::Following
+              } else if let Some(trail) = sniff(&map, pos) {
+                pos.x = trail.x;
+                pos.y = trail.y;
+                CompanionActivity::Tracking
+              } else {
+                CompanionActivity::Idle
+              }
+            }
+            "wait" => {
+              if !can_see_player {
+                noise.emit(pos.x, pos.y, 5, Some(entity), "a dog whining");
+              }
+              CompanionActivity::Resting
+            }
+            _ => CompanionActivity::Idle,
+          }
         }
+      };
+
+      if here != Point::new(pos.x, pos.y) {
+        viewshed.dirty = true;
+        events.emit(GameEvent::Moved {
+          entity,
+          to: Point::new(pos.x, pos.y),
+        });
+      }
+      if activity != moonshot.activity {
+        moonshot.activity = activity;
+        events.emit(GameEvent::ActivityChanged { entity, activity });
       }
     }
   }
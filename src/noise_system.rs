@@ -0,0 +1,186 @@
+use super::{GameEvent, GameEvents, Hearing, Investigate, Map, Player, Position, Weather};
+use rltk::Point;
+use specs::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+pub struct Noise {
+  pub x: i32,
+  pub y: i32,
+  pub volume: i32,
+  pub source: Option<Entity>,
+  pub description: String,
+}
+
+#[derive(Default)]
+pub struct NoiseQueue {
+  pub noises: Vec<Noise>,
+}
+
+impl NoiseQueue {
+  pub fn emit(&mut self, x: i32, y: i32, volume: i32, source: Option<Entity>, description: &str) {
+    self.noises.push(Noise {
+      x,
+      y,
+      volume,
+      source,
+      description: description.to_string(),
+    });
+  }
+}
+
+/// Spreads a noise out from its origin, returning how loud it is on every tile it reaches.
+pub fn propagate(map: &Map, x: i32, y: i32, volume: i32) -> HashMap<usize, i32> {
+  let mut loudness: HashMap<usize, i32> = HashMap::new();
+  let mut open = BinaryHeap::new();
+  let start = map.xy_idx(x, y);
+  loudness.insert(start, volume);
+  open.push((volume, Reverse(start)));
+
+  while let Some((level, Reverse(idx))) = open.pop() {
+    if loudness.get(&idx).is_some_and(|l| *l > level) {
+      continue;
+    }
+    let cx = idx as i32 % map.width;
+    let cy = idx as i32 / map.width;
+    for dy in -1..=1 {
+      for dx in -1..=1 {
+        let (nx, ny) = (cx + dx, cy + dy);
+        if (dx == 0 && dy == 0) || nx < 0 || nx >= map.width || ny < 0 || ny >= map.height {
+          continue;
+        }
+        let nidx = map.xy_idx(nx, ny);
+        let next = level - map.sound_cost(nidx);
+        if next > 0 && loudness.get(&nidx).is_none_or(|l| *l < next) {
+          loudness.insert(nidx, next);
+          open.push((next, Reverse(nidx)));
+        }
+      }
+    }
+  }
+
+  loudness
+}
+
+pub struct HearingSystem {}
+
+impl<'a> System<'a> for HearingSystem {
+  type SystemData = (
+    ReadExpect<'a, Map>,
+    ReadExpect<'a, Weather>,
+    WriteExpect<'a, NoiseQueue>,
+    WriteExpect<'a, GameEvents>,
+    Entities<'a>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Hearing>,
+    ReadStorage<'a, Player>,
+    WriteStorage<'a, Investigate>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (
+      map,
+      weather,
+      mut queue,
+      mut events,
+      entities,
+      positions,
+      hearing,
+      players,
+      mut investigate,
+    ) = data;
+
+    for noise in queue.noises.drain(..) {
+      for (entity, pos, ears) in (&entities, &positions, &hearing).join() {
+        if noise.source == Some(entity) {
+          continue;
+        }
+        // Keen ears pick the sound up further away than it would otherwise carry, and the
+        // weather can drown it out or blow it along
+        let source = Point::new(noise.x, noise.y);
+        let volume = noise.volume + ears.acuity + weather.hearing(source, Point::new(pos.x, pos.y));
+        let loudness = propagate(&map, noise.x, noise.y, volume);
+        if !loudness.contains_key(&map.xy_idx(pos.x, pos.y)) {
+          continue;
+        }
+
+        events.emit(GameEvent::HeardNoise {
+          listener: entity,
+          source,
+          description: noise.description.clone(),
+        });
+        if players.get(entity).is_none() {
+          investigate
+            .insert(
+              entity,
+              Investigate {
+                x: noise.x,
+                y: noise.y,
+              },
+            )
+            .expect("Unable to insert investigate target");
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::TileType;
+
+  /// A one tile high strip of map, so sound has no way around anything in it.
+  fn strip(glyphs: &str) -> Map {
+    let tiles: Vec<TileType> = glyphs.chars().map(TileType::tile_from_glyph).collect();
+    Map {
+      width: tiles.len() as i32,
+      height: 1,
+      tiles,
+      ..Default::default()
+    }
+  }
+
+  fn loudness_at(map: &Map, volume: i32, x: i32) -> Option<i32> {
+    propagate(map, 0, 0, volume).get(&map.xy_idx(x, 0)).copied()
+  }
+
+  #[test]
+  fn open_ground_loses_one_a_tile() {
+    let map = strip("..........");
+    assert_eq!(loudness_at(&map, 10, 0), Some(10));
+    assert_eq!(loudness_at(&map, 10, 4), Some(6));
+    assert_eq!(loudness_at(&map, 10, 9), Some(1));
+    assert_eq!(loudness_at(&map, 5, 9), None);
+  }
+
+  #[test]
+  fn walls_dampen_sound() {
+    let open = strip("..........");
+    let walled = strip("....|.....");
+    assert_eq!(loudness_at(&walled, 10, 3), loudness_at(&open, 10, 3));
+    assert_eq!(loudness_at(&walled, 10, 4), Some(1));
+    assert_eq!(loudness_at(&walled, 10, 5), None);
+  }
+
+  #[test]
+  fn closed_doors_dampen_more_than_open_ones() {
+    let open_door = strip("....+.....");
+    let mut closed_door = strip("....+.....");
+    closed_door.view_blocked.insert(4);
+    assert_eq!(loudness_at(&open_door, 10, 4), Some(6));
+    assert_eq!(loudness_at(&closed_door, 10, 4), Some(3));
+    assert_eq!(loudness_at(&open_door, 10, 7), Some(3));
+    assert_eq!(loudness_at(&closed_door, 10, 7), None);
+  }
+
+  #[test]
+  fn sound_takes_the_quietest_way_round() {
+    // The wall is only in the top row, so the sound goes under it
+    let mut map = strip("....|.....");
+    map.height = 2;
+    map.tiles.extend(vec![TileType::Floor; 10]);
+    let loudness = propagate(&map, 0, 0, 10);
+    assert_eq!(loudness.get(&map.xy_idx(5, 0)).copied(), Some(5));
+  }
+}
@@ -0,0 +1,101 @@
+use super::{layers, ParticleLifetime, Position, Renderable};
+use rltk::RGB;
+use specs::prelude::*;
+
+/// Drawn over everything else on the tile.
+const PARTICLE_ORDER: i32 = -1;
+
+struct ParticleRequest {
+  x: i32,
+  y: i32,
+  fg: RGB,
+  glyph: rltk::FontCharType,
+  lifetime_ms: f32,
+}
+
+/// Effects asked for this turn, to be spawned by the `ParticleSpawnSystem`.
+#[derive(Default)]
+pub struct ParticleBuilder {
+  requests: Vec<ParticleRequest>,
+}
+
+impl ParticleBuilder {
+  pub fn request(&mut self, x: i32, y: i32, fg: RGB, glyph: char, lifetime_ms: f32) {
+    self.requests.push(ParticleRequest {
+      x,
+      y,
+      fg,
+      glyph: rltk::to_cp437(glyph),
+      lifetime_ms,
+    });
+  }
+}
+
+pub struct ParticleSpawnSystem {}
+
+impl<'a> System<'a> for ParticleSpawnSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteExpect<'a, ParticleBuilder>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, Renderable>,
+    WriteStorage<'a, ParticleLifetime>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (entities, mut particles, mut positions, mut renderables, mut lifetimes) = data;
+
+    for request in particles.requests.drain(..) {
+      let particle = entities.create();
+      positions
+        .insert(
+          particle,
+          Position {
+            x: request.x,
+            y: request.y,
+          },
+        )
+        .expect("Unable to insert position");
+      renderables
+        .insert(
+          particle,
+          Renderable {
+            glyph: request.glyph,
+            sprite: None,
+            fg: request.fg,
+            bg: layers::clear(),
+            render_order: PARTICLE_ORDER,
+          },
+        )
+        .expect("Unable to insert renderable");
+      lifetimes
+        .insert(
+          particle,
+          ParticleLifetime {
+            lifetime_ms: request.lifetime_ms,
+          },
+        )
+        .expect("Unable to insert lifetime");
+    }
+  }
+}
+
+/// Ages every particle by the frame time and removes the ones that have run out.
+pub fn cull_dead_particles(ecs: &mut World, frame_time_ms: f32) {
+  let mut dead: Vec<Entity> = Vec::new();
+  {
+    let entities = ecs.entities();
+    let mut lifetimes = ecs.write_storage::<ParticleLifetime>();
+    for (entity, lifetime) in (&entities, &mut lifetimes).join() {
+      lifetime.lifetime_ms -= frame_time_ms;
+      if lifetime.lifetime_ms < 0.0 {
+        dead.push(entity);
+      }
+    }
+  }
+  for particle in dead.iter() {
+    ecs
+      .delete_entity(*particle)
+      .expect("Unable to delete particle");
+  }
+}
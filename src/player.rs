@@ -1,11 +1,11 @@
 use super::{
-  BlocksTile, BlocksVisibility, Door, GameLog, Map, Moonshot, Name, Player, Position, RunState,
-  State, Viewshed,
+  camera, gui, travel, Action, BlocksTile, BlocksVisibility, DebugOverlay, Door, GameEvent,
+  GameEvents, GameLog, Item, KeyBindings, LogCategory, LogLine, Map, Minimap, Moonshot, MoveDelay,
+  Player, Position, RunState, Stamina, State, TileType, Viewshed, WantsToPickup,
 };
 use rltk::Point;
-use rltk::{Rltk, VirtualKeyCode};
+use rltk::Rltk;
 use specs::prelude::*;
-use std::cmp::{max, min};
 
 pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
   let mut doors = ecs.write_storage::<Door>();
@@ -15,16 +15,40 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
   let mut players = ecs.write_storage::<Player>();
   let mut viewsheds = ecs.write_storage::<Viewshed>();
   let mut ppos = ecs.write_resource::<Point>();
-  let mut gamelog = ecs.write_resource::<GameLog>();
+  let mut events = ecs.write_resource::<GameEvents>();
+  let entities = ecs.entities();
   let moonshots = ecs.read_storage::<Moonshot>();
-  let names = ecs.read_storage::<Name>();
+  let stamina = ecs.read_storage::<Stamina>();
   let map = ecs.fetch::<Map>();
 
-  for (_player, pos, viewshed) in (&mut players, &mut positions, &mut viewsheds).join() {
-    let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
+  // The tile a step from `pos` would land on, kept inside the map
+  let step = |pos: &Position| {
+    map.xy_idx(
+      (pos.x + delta_x).clamp(0, map.width - 1),
+      (pos.y + delta_y).clamp(0, map.height - 1),
+    )
+  };
+
+  for (entity, _player, pos, viewshed) in
+    (&entities, &mut players, &mut positions, &mut viewsheds).join()
+  {
+    let destination_idx = step(pos);
+
+    // Setting off into deep water takes some energy left to swim with
+    let here = map.tiles[map.xy_idx(pos.x, pos.y)];
+    if map.tiles[destination_idx] == TileType::DeepWater
+      && here != TileType::DeepWater
+      && stamina.get(entity).is_some_and(|s| s.current == 0)
+    {
+      ecs
+        .write_resource::<GameLog>()
+        .log(LogLine::new(LogCategory::Warning).text("You are too tired to swim."));
+      continue;
+    }
+
     if !map.blocked[destination_idx] {
-      pos.x = min(79, max(0, pos.x + delta_x));
-      pos.y = min(49, max(0, pos.y + delta_y));
+      pos.x = (pos.x + delta_x).clamp(0, map.width - 1);
+      pos.y = (pos.y + delta_y).clamp(0, map.height - 1);
 
       // Update PlayerPosition resource
       ppos.x = pos.x;
@@ -32,17 +56,27 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
 
       // Mark viewshed for recalc
       viewshed.dirty = true;
+
+      events.emit(GameEvent::Moved {
+        entity,
+        to: Point::new(pos.x, pos.y),
+      });
     }
 
-    let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
+    let destination_idx = step(pos);
 
     // Handle colliding into specific objects
     for potential_target in map.tile_content[destination_idx].iter() {
       let door = doors.get_mut(*potential_target);
       let _moonshot = moonshots.get(*potential_target);
-      let name = names.get(*potential_target);
 
       if let Some(door) = door {
+        if !door.open {
+          events.emit(GameEvent::DoorOpened {
+            entity,
+            door: *potential_target,
+          });
+        }
         door.open = true;
         blocks_visibility.remove(*potential_target);
         blocks_movement.remove(*potential_target);
@@ -50,49 +84,142 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
         // glyph.glyph = rltk::to_cp437('/');
         viewshed.dirty = true;
       } else if let Some(_moonshot) = _moonshot {
-        match name {
-          Some(name) => gamelog
-            .entries
-            .push(format!("You bump into {:?}", name.name)),
-          None => gamelog.entries.push(format!("You bump into something")),
-        }
+        events.emit(GameEvent::Bumped {
+          entity,
+          target: *potential_target,
+        });
       }
     }
   }
 }
 
+/// Picks up whatever is underfoot. Finding nothing there costs no turn.
+fn get_item(ecs: &mut World) -> RunState {
+  let player_pos = ecs.fetch::<Point>();
+  let player_entity = ecs.fetch::<Entity>();
+  let map = ecs.fetch::<Map>();
+  let items = ecs.read_storage::<Item>();
+  let mut gamelog = ecs.write_resource::<GameLog>();
+
+  let idx = map.xy_idx(player_pos.x, player_pos.y);
+  let target_item = map.tile_content[idx]
+    .iter()
+    .find(|entity| items.get(**entity).is_some());
+
+  match target_item {
+    None => {
+      gamelog.log(LogLine::new(LogCategory::Warning).text("There is nothing here to pick up."));
+      RunState::AwaitingInput
+    }
+    Some(item) => {
+      let mut pickup = ecs.write_storage::<WantsToPickup>();
+      pickup
+        .insert(
+          *player_entity,
+          WantsToPickup {
+            collected_by: *player_entity,
+            item: *item,
+          },
+        )
+        .expect("Unable to insert want to pickup");
+      RunState::PlayerTurn
+    }
+  }
+}
+
+/// Uses up a turn the player lost to heavy going, letting everyone else act again. Returns false
+/// if there was none to lose.
+pub fn lose_turn(ecs: &mut World) -> bool {
+  let player_entity = *ecs.fetch::<Entity>();
+  let mut delays = ecs.write_storage::<MoveDelay>();
+  let delay = match delays.get_mut(player_entity) {
+    Some(delay) => delay,
+    None => return false,
+  };
+  delay.turns -= 1;
+  if delay.turns <= 0 {
+    delays.remove(player_entity);
+  }
+  true
+}
+
+/// The longest a rest lasts when nothing disturbs it.
+const REST_TURNS: i32 = 50;
+
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-  // Player movement
-  match ctx.key {
+  // Click on the map to walk there
+  if ctx.left_click {
+    if let Some(target) = camera::to_map(&gs.ecs, ctx.mouse_point()) {
+      travel::start_travel(&mut gs.ecs, target);
+    }
+    return RunState::AwaitingInput;
+  }
+
+  let action = match gs.ecs.fetch::<KeyBindings>().action(ctx) {
     None => return RunState::AwaitingInput, // Nothing happened
-    Some(key) => match key {
-      VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => {
-        try_move_player(-1, 0, &mut gs.ecs)
-      }
+    Some(action) => action,
+  };
 
-      VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => {
-        try_move_player(1, 0, &mut gs.ecs)
-      }
+  // Player movement
+  if let Some((delta_x, delta_y)) = action.direction() {
+    if ctx.shift {
+      return travel::start_run(&mut gs.ecs, delta_x, delta_y);
+    }
+    try_move_player(delta_x, delta_y, &mut gs.ecs);
+    return RunState::PlayerTurn;
+  }
 
-      VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
-        try_move_player(0, -1, &mut gs.ecs)
-      }
+  match action {
+    // Let time pass
+    Action::Wait => {}
+    Action::Rest => return travel::start_rest(&mut gs.ecs, REST_TURNS),
+
+    // Items
+    Action::Pickup => return get_item(&mut gs.ecs),
+    Action::Inventory => return RunState::ShowInventory,
+    Action::Drop => return RunState::ShowDropItem,
+    Action::Throw => return RunState::ShowThrowItem,
+
+    // Head off to wherever hasn't been seen yet
+    Action::Explore => {
+      travel::start_explore(&mut gs.ecs);
+      return RunState::AwaitingInput;
+    }
 
-      VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
-        try_move_player(0, 1, &mut gs.ecs)
-      }
+    // Look around
+    Action::Look => {
+      let player_pos = *gs.ecs.fetch::<Point>();
+      let mut cursor = gs.ecs.write_resource::<gui::LookCursor>();
+      cursor.x = player_pos.x;
+      cursor.y = player_pos.y;
+      return RunState::LookMode;
+    }
 
-      // Diagonals
-      VirtualKeyCode::Numpad9 | VirtualKeyCode::Y => try_move_player(1, -1, &mut gs.ecs),
+    // Message history
+    Action::MessageLog => {
+      let mut viewer = gs.ecs.write_resource::<gui::LogViewer>();
+      viewer.offset = 0;
+      viewer.searching = false;
+      return RunState::ShowLog;
+    }
 
-      VirtualKeyCode::Numpad7 | VirtualKeyCode::U => try_move_player(-1, -1, &mut gs.ecs),
+    Action::KeyBindings => return RunState::ShowBindings,
+    Action::Pause => return RunState::PauseMenu { selection: 0 },
 
-      VirtualKeyCode::Numpad3 | VirtualKeyCode::N => try_move_player(1, 1, &mut gs.ecs),
+    Action::Minimap => {
+      let mut minimap = gs.ecs.write_resource::<Minimap>();
+      minimap.shown = !minimap.shown;
+      return RunState::AwaitingInput;
+    }
 
-      VirtualKeyCode::Numpad1 | VirtualKeyCode::B => try_move_player(-1, 1, &mut gs.ecs),
+    // Debug overlays
+    Action::ScentOverlay => {
+      let mut overlay = gs.ecs.write_resource::<DebugOverlay>();
+      overlay.scent = !overlay.scent;
+      return RunState::AwaitingInput;
+    }
 
-      _ => return RunState::AwaitingInput,
-    },
+    _ => return RunState::AwaitingInput,
   }
   RunState::PlayerTurn
 }
@@ -0,0 +1,99 @@
+use rltk::RandomNumberGenerator;
+
+pub struct RandomEntry {
+  name: String,
+  weight: i32,
+}
+
+impl RandomEntry {
+  pub fn new<S: ToString>(name: S, weight: i32) -> RandomEntry {
+    RandomEntry {
+      name: name.to_string(),
+      weight,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct RandomTable {
+  entries: Vec<RandomEntry>,
+  total_weight: i32,
+}
+
+impl RandomTable {
+  pub fn new() -> RandomTable {
+    RandomTable {
+      entries: Vec::new(),
+      total_weight: 0,
+    }
+  }
+
+  pub fn add<S: ToString>(mut self, name: S, weight: i32) -> RandomTable {
+    if weight > 0 {
+      self.total_weight += weight;
+      self.entries.push(RandomEntry::new(name, weight));
+    }
+    self
+  }
+
+  /// The names that can be rolled, apart from "None".
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self
+      .entries
+      .iter()
+      .map(|e| e.name.as_str())
+      .filter(|name| *name != "None")
+  }
+
+  /// Picks an entry by weight, or "None" if the table is empty.
+  pub fn roll(&self, rng: &mut RandomNumberGenerator) -> String {
+    if self.total_weight == 0 {
+      return "None".to_string();
+    }
+    let mut roll = rng.roll_dice(1, self.total_weight) - 1;
+    let mut index: usize = 0;
+
+    while roll > 0 {
+      if roll < self.entries[index].weight {
+        return self.entries[index].name.clone();
+      }
+
+      roll -= self.entries[index].weight;
+      index += 1;
+    }
+
+    self.entries[index].name.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn an_empty_table_rolls_none() {
+    let mut rng = RandomNumberGenerator::seeded(1);
+    assert_eq!(RandomTable::new().roll(&mut rng), "None");
+  }
+
+  #[test]
+  fn entries_without_weight_are_never_rolled() {
+    let mut rng = RandomNumberGenerator::seeded(2);
+    let table = RandomTable::new().add("Rabbit", 0).add("Duck", 1);
+    for _ in 0..100 {
+      assert_eq!(table.roll(&mut rng), "Duck");
+    }
+    assert_eq!(table.names().collect::<Vec<_>>(), vec!["Duck"]);
+  }
+
+  #[test]
+  fn rolls_follow_the_weights() {
+    let mut rng = RandomNumberGenerator::seeded(3);
+    let table = RandomTable::new().add("Rabbit", 1).add("None", 3);
+    let rabbits = (0..4000)
+      .filter(|_| table.roll(&mut rng) == "Rabbit")
+      .count();
+    assert!((800..1200).contains(&rabbits), "{} rabbits", rabbits);
+    assert_eq!(table.names().collect::<Vec<_>>(), vec!["Rabbit"]);
+  }
+}
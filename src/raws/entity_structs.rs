@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Raws {
+  pub entities: Vec<EntityTemplate>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EntityTemplate {
+  pub id: String,
+  pub name: Option<String>,
+  pub description: Option<String>,
+  #[serde(default)]
+  pub player: bool,
+  pub renderable: Option<RenderableTemplate>,
+  pub viewshed: Option<i32>,
+  pub hearing: Option<i32>,
+  pub scent: Option<i32>,
+  pub stamina: Option<i32>,
+  #[serde(default)]
+  pub item: bool,
+  pub throwable: Option<i32>,
+  #[serde(default)]
+  pub blocks_tile: bool,
+  #[serde(default)]
+  pub blocks_visibility: bool,
+  pub door: Option<DoorTemplate>,
+  pub ai: Option<AiTemplate>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RenderableTemplate {
+  pub glyph: String,
+  pub sprite: Option<u16>,
+  pub fg: String,
+  pub bg: String,
+  pub order: i32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DoorTemplate {
+  pub open: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AiTemplate {
+  Companion { actions: Vec<String> },
+  Wildlife { fear_range: i32 },
+}
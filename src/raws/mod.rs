@@ -0,0 +1,28 @@
+mod entity_structs;
+mod rawmaster;
+
+pub use rawmaster::*;
+use std::sync::Mutex;
+
+lazy_static! {
+  pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::empty());
+}
+
+rltk::embedded_resource!(RAW_FILE, "../../raws/spawns.json");
+
+/// Parses and validates the embedded entity templates, describing the first problem found.
+pub fn load_raws() -> Result<(), String> {
+  rltk::link_resource!(RAW_FILE, "../../raws/spawns.json");
+
+  let raw_data = rltk::embedding::EMBED
+    .lock()
+    .get_resource("../../raws/spawns.json".to_string())
+    .ok_or_else(|| "raws/spawns.json: not embedded in the build".to_string())?;
+  let raw_string = std::str::from_utf8(raw_data).map_err(|e| format!("raws/spawns.json: {}", e))?;
+
+  RAWS
+    .lock()
+    .unwrap()
+    .load(raw_string)
+    .map_err(|e| format!("raws/spawns.json: {}", e))
+}
@@ -0,0 +1,302 @@
+use super::entity_structs::{AiTemplate, EntityTemplate, Raws};
+use super::RAWS;
+use crate::components::*;
+use crate::moonshot_ai::COMPANION_ACTIONS;
+use crate::spawners::wildlife_keys;
+use crate::tileset::TILE_SHEET_SPRITES;
+use rltk::RGB;
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Templates the game spawns by name when it sets up a new game.
+const REQUIRED_TEMPLATES: [&str; 3] = ["player", "moonshot", "door"];
+
+pub struct RawMaster {
+  raws: Raws,
+  entity_index: HashMap<String, usize>,
+}
+
+impl RawMaster {
+  pub fn empty() -> RawMaster {
+    RawMaster {
+      raws: Raws {
+        entities: Vec::new(),
+      },
+      entity_index: HashMap::new(),
+    }
+  }
+
+  /// Replaces the loaded templates with the ones in `data`, if they are all valid.
+  pub fn load(&mut self, data: &str) -> Result<(), String> {
+    let raws: Raws = serde_json::from_str(data).map_err(|e| e.to_string())?;
+
+    let mut entity_index = HashMap::new();
+    for (i, template) in raws.entities.iter().enumerate() {
+      if template.id.is_empty() {
+        return Err(format!("entity #{} has an empty id", i + 1));
+      }
+      validate(template).map_err(|e| format!("entity '{}': {}", template.id, e))?;
+      if entity_index.insert(template.id.clone(), i).is_some() {
+        return Err(format!(
+          "entity '{}' is defined more than once",
+          template.id
+        ));
+      }
+    }
+
+    for key in REQUIRED_TEMPLATES.iter() {
+      if !entity_index.contains_key(*key) {
+        return Err(format!("entity '{}' is required but not defined", key));
+      }
+    }
+    if !raws.entities[entity_index["player"]].player {
+      return Err("entity 'player' must have \"player\": true".to_string());
+    }
+    for key in wildlife_keys() {
+      if !entity_index.contains_key(&key) {
+        return Err(format!(
+          "the spawn tables roll entity '{}', which is not defined",
+          key
+        ));
+      }
+    }
+
+    self.raws = raws;
+    self.entity_index = entity_index;
+    Ok(())
+  }
+}
+
+fn parse_colour(field: &str, hex: &str) -> Result<RGB, String> {
+  RGB::from_hex(hex).map_err(|_| format!("{} colour '{}' is not a #RRGGBB value", field, hex))
+}
+
+fn validate(template: &EntityTemplate) -> Result<(), String> {
+  if let Some(renderable) = &template.renderable {
+    if renderable.glyph.chars().count() != 1 {
+      return Err(format!(
+        "glyph '{}' must be exactly one character",
+        renderable.glyph
+      ));
+    }
+    if let Some(sprite) = renderable.sprite {
+      if sprite >= TILE_SHEET_SPRITES {
+        return Err(format!(
+          "sprite {} is past the end of the tile sheet",
+          sprite
+        ));
+      }
+    }
+    parse_colour("fg", &renderable.fg)?;
+    parse_colour("bg", &renderable.bg)?;
+  }
+  if template.player && template.viewshed.is_none() {
+    return Err("the player needs a viewshed".to_string());
+  }
+  if let Some(range) = template.viewshed {
+    if range < 1 {
+      return Err(format!("viewshed range {} must be at least 1", range));
+    }
+  }
+  if let Some(stamina) = template.stamina {
+    if stamina < 1 {
+      return Err(format!("stamina {} must be at least 1", stamina));
+    }
+  }
+  if let Some(range) = template.throwable {
+    if !template.item {
+      return Err("only items can be throwable".to_string());
+    }
+    if range < 1 {
+      return Err(format!("throwable range {} must be at least 1", range));
+    }
+  }
+  match &template.ai {
+    Some(AiTemplate::Companion { actions }) => {
+      if actions.is_empty() {
+        return Err("companion ai needs at least one action".to_string());
+      }
+      for action in actions.iter() {
+        if !COMPANION_ACTIONS.contains(&action.as_str()) {
+          return Err(format!(
+            "unknown companion action '{}', expected one of {:?}",
+            action, COMPANION_ACTIONS
+          ));
+        }
+      }
+    }
+    Some(AiTemplate::Wildlife { fear_range }) if *fear_range < 1 => {
+      return Err(format!(
+        "wildlife fear_range {} must be at least 1",
+        fear_range
+      ));
+    }
+    _ => {}
+  }
+  Ok(())
+}
+
+/// Builds the entity described by the template `key` at x, y. Returns None for unknown keys.
+pub fn spawn_named(ecs: &mut World, key: &str, x: i32, y: i32) -> Option<Entity> {
+  let raws = RAWS.lock().unwrap();
+  let template = &raws.raws.entities[*raws.entity_index.get(key)?];
+
+  let mut eb = ecs.create_entity().with(Position { x, y });
+
+  if let Some(name) = &template.name {
+    eb = eb.with(Name { name: name.clone() });
+  }
+  if let Some(text) = &template.description {
+    eb = eb.with(Description { text: text.clone() });
+  }
+  if template.player {
+    eb = eb.with(Player {});
+  }
+  if let Some(renderable) = &template.renderable {
+    eb = eb.with(Renderable {
+      glyph: rltk::to_cp437(renderable.glyph.chars().next().unwrap()),
+      sprite: renderable.sprite,
+      fg: RGB::from_hex(&renderable.fg).expect("Colour validated at load"),
+      bg: RGB::from_hex(&renderable.bg)
+        .expect("Colour validated at load")
+        .into(),
+      render_order: renderable.order,
+    });
+  }
+  if let Some(range) = template.viewshed {
+    eb = eb.with(Viewshed {
+      visible_tiles: Vec::new(),
+      range,
+      dirty: true,
+    });
+  }
+  if let Some(acuity) = template.hearing {
+    eb = eb.with(Hearing { acuity });
+  }
+  if let Some(strength) = template.scent {
+    eb = eb.with(LeavesScent { strength });
+  }
+  if let Some(max) = template.stamina {
+    eb = eb.with(Stamina { current: max, max });
+  }
+  if template.item {
+    eb = eb.with(Item {});
+  }
+  if let Some(range) = template.throwable {
+    eb = eb.with(Throwable { range });
+  }
+  if template.blocks_tile {
+    eb = eb.with(BlocksTile {});
+  }
+  if template.blocks_visibility {
+    eb = eb.with(BlocksVisibility {});
+  }
+  if let Some(door) = &template.door {
+    eb = eb.with(Door { open: door.open });
+  }
+  match &template.ai {
+    Some(AiTemplate::Companion { actions }) => {
+      eb = eb.with(Moonshot {
+        actions: actions.clone(),
+        activity: CompanionActivity::Idle,
+        saw_player: false,
+      });
+    }
+    Some(AiTemplate::Wildlife { fear_range }) => {
+      eb = eb.with(Wildlife {
+        fear_range: *fear_range,
+      });
+    }
+    None => {}
+  }
+
+  Some(eb.build())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::Value;
+
+  const SPAWNS: &str = include_str!("../../raws/spawns.json");
+
+  /// Loads the real templates after letting `edit` change them.
+  fn load_edited(edit: impl FnOnce(&mut Vec<Value>)) -> Result<(), String> {
+    let mut raws: Value = serde_json::from_str(SPAWNS).unwrap();
+    edit(raws["entities"].as_array_mut().unwrap());
+    RawMaster::empty().load(&raws.to_string())
+  }
+
+  fn template<'a>(entities: &'a mut [Value], id: &str) -> &'a mut Value {
+    entities.iter_mut().find(|e| e["id"] == id).unwrap()
+  }
+
+  #[test]
+  fn the_shipped_templates_load() {
+    assert_eq!(RawMaster::empty().load(SPAWNS), Ok(()));
+  }
+
+  #[test]
+  fn required_templates_must_be_defined() {
+    for key in REQUIRED_TEMPLATES.iter() {
+      assert_eq!(
+        load_edited(|entities| entities.retain(|e| e["id"] != *key)),
+        Err(format!("entity '{}' is required but not defined", key))
+      );
+    }
+  }
+
+  #[test]
+  fn the_player_template_must_be_the_player() {
+    assert_eq!(
+      load_edited(|entities| template(entities, "player")["player"] = Value::Bool(false)),
+      Err("entity 'player' must have \"player\": true".to_string())
+    );
+  }
+
+  #[test]
+  fn the_player_needs_a_viewshed() {
+    let result = load_edited(|entities| {
+      template(entities, "player")
+        .as_object_mut()
+        .unwrap()
+        .remove("viewshed");
+    });
+    assert_eq!(
+      result,
+      Err("entity 'player': the player needs a viewshed".to_string())
+    );
+  }
+
+  #[test]
+  fn everything_the_spawn_tables_roll_must_be_defined() {
+    let key = wildlife_keys().remove(0);
+    assert_eq!(
+      load_edited(|entities| entities.retain(|e| e["id"] != key.as_str())),
+      Err(format!(
+        "the spawn tables roll entity '{}', which is not defined",
+        key
+      ))
+    );
+  }
+
+  #[test]
+  fn ids_must_be_unique() {
+    let result = load_edited(|entities| {
+      let copy = template(entities, "door").clone();
+      entities.push(copy);
+    });
+    assert_eq!(
+      result,
+      Err("entity 'door' is defined more than once".to_string())
+    );
+  }
+
+  #[test]
+  fn a_failed_load_keeps_the_old_templates() {
+    let mut raws = RawMaster::empty();
+    raws.load(SPAWNS).unwrap();
+    assert!(raws.load("{ \"entities\": [] }").is_err());
+    assert!(raws.entity_index.contains_key("player"));
+  }
+}
@@ -23,4 +23,9 @@ impl Rect {
   pub fn center(&self) -> (i32, i32) {
     ((self.x1 + self.x2) / 2, (self.y1 + self.y2) / 2)
   }
+
+  /// Whether the point lies inside the rectangle or on its edge.
+  pub fn contains(&self, x: i32, y: i32) -> bool {
+    x >= self.x1 && x <= self.x2 && y >= self.y1 && y <= self.y2
+  }
 }
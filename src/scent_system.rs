@@ -0,0 +1,61 @@
+use super::{LeavesScent, Map, Position, RunState, Weather, MAX_SCENT};
+use specs::prelude::*;
+
+/// Strength a scent loses for each tile the wind carries it.
+const WIND_SCENT_LOSS: i32 = 15;
+
+pub struct ScentSystem {}
+
+impl<'a> System<'a> for ScentSystem {
+  type SystemData = (
+    WriteExpect<'a, Map>,
+    ReadExpect<'a, RunState>,
+    ReadExpect<'a, Weather>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, LeavesScent>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (mut map, runstate, weather, positions, scents) = data;
+
+    // Scent fades once per full turn, faster out in the rain
+    if *runstate == RunState::PlayerTurn {
+      for idx in 0..map.scent.len() {
+        let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+        let fade = if map.is_indoors(x, y) {
+          1
+        } else {
+          weather.scent_fade()
+        };
+        map.scent[idx] = i32::max(0, map.scent[idx] - fade);
+      }
+
+      // The wind carries outdoor scent a tile downwind, weakening as it goes
+      if let Some(wind) = weather.wind() {
+        let before = map.scent.clone();
+        for (idx, strength) in before.iter().enumerate() {
+          let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+          let (nx, ny) = (x + wind.x, y + wind.y);
+          if *strength <= WIND_SCENT_LOSS
+            || nx < 0
+            || nx >= map.width
+            || ny < 0
+            || ny >= map.height
+            || map.is_indoors(x, y)
+          {
+            continue;
+          }
+          let nidx = map.xy_idx(nx, ny);
+          if !map.tiles[nidx].is_blocked() {
+            map.scent[nidx] = i32::max(map.scent[nidx], strength - WIND_SCENT_LOSS);
+          }
+        }
+      }
+    }
+
+    for (pos, scent) in (&positions, &scents).join() {
+      let idx = map.xy_idx(pos.x, pos.y);
+      map.scent[idx] = i32::min(MAX_SCENT, i32::max(map.scent[idx], scent.strength));
+    }
+  }
+}
@@ -0,0 +1,96 @@
+use super::Tileset;
+use serde::{Deserialize, Serialize};
+
+/// Where the options chosen in game are kept between sessions.
+const SETTINGS_FILE: &str = "settings.json";
+
+/// The fonts the text console can be drawn in.
+#[derive(Serialize, Deserialize, PartialEq, Copy, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Font {
+  #[default]
+  Terminal8x8,
+  Vga8x16,
+}
+
+impl Font {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Font::Terminal8x8 => "8x8",
+      Font::Vga8x16 => "8x16",
+    }
+  }
+
+  pub fn next(&self) -> Font {
+    match self {
+      Font::Terminal8x8 => Font::Vga8x16,
+      Font::Vga8x16 => Font::Terminal8x8,
+    }
+  }
+
+  /// The font's file, as the context builder knows it.
+  pub fn file(&self) -> &'static str {
+    match self {
+      Font::Terminal8x8 => "terminal8x8.png",
+      Font::Vga8x16 => "vga8x16.png",
+    }
+  }
+
+  /// Width and height of one character in pixels.
+  pub fn size(&self) -> (u32, u32) {
+    match self {
+      Font::Terminal8x8 => (8, 8),
+      Font::Vga8x16 => (8, 16),
+    }
+  }
+}
+
+/// How the game looks. Missing entries in the file keep their defaults and unknown ones are
+/// ignored, so files from older and newer versions still load.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+  pub tileset: Tileset,
+  pub font: Font,
+  /// Draw scanlines over the screen, as on an old CRT monitor.
+  pub scanlines: bool,
+  /// Let bright glyphs bleed into their neighbours. Only shows with scanlines on.
+  pub screen_burn: bool,
+  /// Keep water and grass still.
+  pub reduce_motion: bool,
+  /// The font the window was opened with. Changing fonts needs a restart.
+  #[serde(skip)]
+  pub font_in_use: Font,
+}
+
+impl Settings {
+  /// Writes the settings back to the file. A failure is only reported, since the game can carry
+  /// on with the settings it has in memory.
+  pub fn save(&self) {
+    let written = serde_json::to_string_pretty(self)
+      .map_err(|e| e.to_string())
+      .and_then(|text| std::fs::write(SETTINGS_FILE, text).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+      rltk::console::log(format!("Could not save {}: {}", SETTINGS_FILE, e));
+    }
+  }
+}
+
+/// Reads the settings file, falling back to the defaults if there is none yet. A file that
+/// cannot be read is reported and then ignored, since the game runs fine without it.
+pub fn load_settings() -> Settings {
+  let loaded = match std::fs::read_to_string(SETTINGS_FILE) {
+    Ok(text) => serde_json::from_str(&text).map_err(|e| e.to_string()),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+    Err(e) => Err(e.to_string()),
+  };
+  let mut settings = loaded.unwrap_or_else(|e| {
+    rltk::console::log(format!(
+      "Could not load {}, using defaults: {}",
+      SETTINGS_FILE, e
+    ));
+    Settings::default()
+  });
+  settings.font_in_use = settings.font;
+  settings
+}
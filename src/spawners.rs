@@ -1,76 +1,111 @@
-use super::{
-  BlocksTile, BlocksVisibility, Door, Moonshot, Name, Player, Position, Renderable, Viewshed,
-};
-use rltk::RGB;
+use super::{raws, Map, Position, RandomTable, Rect, TileType};
+use rltk::RandomNumberGenerator;
 use specs::prelude::*;
+use std::cmp::min;
+use std::collections::HashSet;
 
-pub fn player(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Renderable {
-      glyph: rltk::to_cp437('@'),
-      fg: RGB::named(rltk::YELLOW),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 0,
-    })
-    .with(Player {})
-    .with(Name {
-      name: "Player".to_string(),
-    })
-    .with(Viewshed {
-      visible_tiles: Vec::new(),
-      range: 8,
-      dirty: true,
-    })
-    .build();
+const REGION_WIDTH: i32 = 16;
+const REGION_HEIGHT: i32 = 11;
+const MAX_SPAWNS_PER_REGION: i32 = 3;
+
+fn tall_grass_table() -> RandomTable {
+  RandomTable::new().add("rabbit", 6).add("None", 4)
+}
+
+fn shallow_water_table() -> RandomTable {
+  RandomTable::new().add("duck", 5).add("None", 5)
+}
+
+fn woods_table() -> RandomTable {
+  RandomTable::new()
+    .add("bird", 5)
+    .add("rabbit", 1)
+    .add("None", 4)
+}
+
+fn meadow_table() -> RandomTable {
+  RandomTable::new()
+    .add("rabbit", 1)
+    .add("bird", 1)
+    .add("None", 12)
 }
 
-pub fn moonshot(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(Moonshot {
-      actions: vec![
-        "follow_player".to_string(),
-        "wait".to_string(),
-        "explore_nearby".to_string(),
-      ],
-    })
-    .with(Name {
-      name: "Moonshot".to_string(),
-    })
-    .with(BlocksTile {})
-    .with(Viewshed {
-      visible_tiles: Vec::new(),
-      range: 12,
-      dirty: true,
-    })
-    .with(Renderable {
-      glyph: rltk::to_cp437('m'),
-      fg: RGB::named(rltk::PURPLE),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 0,
-    })
-    .build();
+/// Every template the spawn tables can roll.
+pub fn wildlife_keys() -> Vec<String> {
+  let tables = [
+    tall_grass_table(),
+    shallow_water_table(),
+    woods_table(),
+    meadow_table(),
+  ];
+  let mut keys: Vec<String> = tables
+    .iter()
+    .flat_map(|t| t.names().map(|name| name.to_string()))
+    .collect();
+  keys.sort();
+  keys.dedup();
+  keys
 }
 
-pub fn door(ecs: &mut World, x: i32, y: i32) {
-  ecs
-    .create_entity()
-    .with(Position { x, y })
-    .with(BlocksTile {})
-    .with(Renderable {
-      glyph: rltk::to_cp437('+'),
-      fg: RGB::named(rltk::CHOCOLATE),
-      bg: RGB::named(rltk::BLACK),
-      render_order: 2,
-    })
-    .with(Name {
-      name: "Door".to_string(),
-    })
-    // .with(BlocksTile {})
-    .with(BlocksVisibility {})
-    .with(Door { open: false })
-    .build();
+/// Picks the spawn table for the biome a tile belongs to.
+fn spawn_table_for(map: &Map, x: i32, y: i32) -> RandomTable {
+  match map.tiles[map.xy_idx(x, y)] {
+    TileType::TallGrass => tall_grass_table(),
+    TileType::ShallowWater => shallow_water_table(),
+    _ if map.is_near(x, y, TileType::Tree) => woods_table(),
+    _ => meadow_table(),
+  }
+}
+
+/// Rolls a few spawn attempts inside a region, each using the table of the biome it lands in.
+pub fn spawn_region(ecs: &mut World, region: &Rect, occupied: &mut HashSet<usize>) {
+  let mut spawn_points: Vec<(i32, i32, String)> = Vec::new();
+  {
+    let map = ecs.fetch::<Map>();
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let attempts = rng.roll_dice(1, MAX_SPAWNS_PER_REGION + 1) - 1;
+
+    for _i in 0..attempts {
+      let x = rng.range(region.x1, region.x2);
+      let y = rng.range(region.y1, region.y2);
+      let idx = map.xy_idx(x, y);
+      // Nothing lives out in the deep water
+      let tile = map.tiles[idx];
+      if tile.is_blocked() || tile == TileType::DeepWater || occupied.contains(&idx) {
+        continue;
+      }
+
+      let roll = spawn_table_for(&map, x, y).roll(&mut rng);
+      if roll != "None" {
+        occupied.insert(idx);
+        spawn_points.push((x, y, roll));
+      }
+    }
+  }
+
+  for (x, y, key) in spawn_points.iter() {
+    raws::spawn_named(ecs, key, *x, *y);
+  }
+}
+
+/// Populates the whole map with wildlife, one region at a time.
+pub fn spawn_wildlife(ecs: &mut World) {
+  let (width, height, mut occupied) = {
+    let map = ecs.fetch::<Map>();
+    let positions = ecs.read_storage::<Position>();
+    let occupied: HashSet<usize> = positions.join().map(|p| map.xy_idx(p.x, p.y)).collect();
+    (map.width, map.height, occupied)
+  };
+
+  for y in (0..height).step_by(REGION_HEIGHT as usize) {
+    for x in (0..width).step_by(REGION_WIDTH as usize) {
+      let region = Rect::new(
+        x,
+        y,
+        min(REGION_WIDTH, width - x),
+        min(REGION_HEIGHT, height - y),
+      );
+      spawn_region(ecs, &region, &mut occupied);
+    }
+  }
 }
@@ -0,0 +1,120 @@
+use super::{
+  Fetching, GameEvent, GameEvents, InBackpack, Map, Moonshot, NoiseQueue, Position, Projectile,
+  WantsToThrow,
+};
+use rltk::{LineAlg, Point};
+use specs::prelude::*;
+
+/// Milliseconds a thrown item spends on each tile of its flight.
+const FRAME_MS: f32 = 40.0;
+
+pub struct ThrowSystem {}
+
+impl<'a> System<'a> for ThrowSystem {
+  type SystemData = (
+    ReadExpect<'a, Map>,
+    WriteExpect<'a, GameEvents>,
+    Entities<'a>,
+    WriteStorage<'a, WantsToThrow>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, InBackpack>,
+    WriteStorage<'a, Projectile>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (map, mut events, entities, mut wants_throw, mut positions, mut backpack, mut projectiles) =
+      data;
+
+    for (thrower, throw) in (&entities, &wants_throw).join() {
+      let start = match positions.get(thrower) {
+        Some(pos) => Point::new(pos.x, pos.y),
+        None => continue,
+      };
+
+      // Follow the line until something is in the way
+      let mut path = Vec::new();
+      for point in rltk::line2d(LineAlg::Bresenham, start, throw.target) {
+        if point == start {
+          continue;
+        }
+        if point.x < 0 || point.x >= map.width || point.y < 0 || point.y >= map.height {
+          break;
+        }
+        if map.blocked[map.xy_idx(point.x, point.y)] {
+          break;
+        }
+        path.push(point);
+      }
+
+      backpack.remove(throw.item);
+      positions
+        .insert(
+          throw.item,
+          Position {
+            x: start.x,
+            y: start.y,
+          },
+        )
+        .expect("Unable to insert position");
+      projectiles
+        .insert(
+          throw.item,
+          Projectile {
+            path,
+            step: 0,
+            timer: 0.0,
+          },
+        )
+        .expect("Unable to insert projectile");
+
+      events.emit(GameEvent::Thrown {
+        entity: thrower,
+        item: throw.item,
+      });
+    }
+
+    wants_throw.clear();
+  }
+}
+
+/// Moves thrown items along their flight path by frame time. Returns true while any are airborne.
+pub fn animate_projectiles(ecs: &mut World, frame_time_ms: f32) -> bool {
+  let mut landed: Vec<(Entity, Point)> = Vec::new();
+  {
+    let entities = ecs.entities();
+    let mut projectiles = ecs.write_storage::<Projectile>();
+    let mut positions = ecs.write_storage::<Position>();
+
+    for (entity, projectile, pos) in (&entities, &mut projectiles, &mut positions).join() {
+      projectile.timer += frame_time_ms;
+      while projectile.timer > FRAME_MS && projectile.step < projectile.path.len() {
+        projectile.timer -= FRAME_MS;
+        pos.x = projectile.path[projectile.step].x;
+        pos.y = projectile.path[projectile.step].y;
+        projectile.step += 1;
+      }
+      if projectile.step >= projectile.path.len() {
+        landed.push((entity, Point::new(pos.x, pos.y)));
+      }
+    }
+  }
+
+  for (item, at) in landed.iter() {
+    ecs.write_storage::<Projectile>().remove(*item);
+    ecs
+      .write_resource::<NoiseQueue>()
+      .emit(at.x, at.y, 4, None, "something land nearby");
+
+    // Anything that plays fetch goes after it
+    let entities = ecs.entities();
+    let moonshots = ecs.read_storage::<Moonshot>();
+    let mut fetching = ecs.write_storage::<Fetching>();
+    for (dog, _moonshot) in (&entities, &moonshots).join() {
+      fetching
+        .insert(dog, Fetching { item: *item })
+        .expect("Unable to insert fetch target");
+    }
+  }
+
+  (&ecs.read_storage::<Projectile>()).join().next().is_some()
+}
@@ -0,0 +1,59 @@
+use super::layers;
+use rltk::{Rltk, RGB, RGBA};
+use serde::{Deserialize, Serialize};
+
+/// The bundled tile sheet: 16x16 pixel sprites, indexed left to right and top to bottom.
+pub const TILE_SHEET: &str = "example_tiles.jpg";
+pub const TILE_SHEET_SPRITES: u16 = 256;
+
+rltk::embedded_resource!(TILE_SHEET_FILE, "../resources/example_tiles.jpg");
+
+/// Makes the embedded tile sheet available to the context builder.
+pub fn link_tile_sheet() {
+  rltk::link_resource!(TILE_SHEET_FILE, "resources/example_tiles.jpg");
+}
+
+/// How the map and everything on it is drawn.
+#[derive(Serialize, Deserialize, PartialEq, Copy, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Tileset {
+  #[default]
+  Ascii,
+  Graphical,
+}
+
+impl Tileset {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Tileset::Ascii => "ASCII",
+      Tileset::Graphical => "Graphical",
+    }
+  }
+
+  pub fn next(&self) -> Tileset {
+    match self {
+      Tileset::Ascii => Tileset::Graphical,
+      Tileset::Graphical => Tileset::Ascii,
+    }
+  }
+
+  /// The sprite to draw in place of a glyph, if this tileset uses sprites and one was given.
+  pub fn sprite(&self, sprite: Option<u16>) -> Option<u16> {
+    match self {
+      Tileset::Ascii => None,
+      Tileset::Graphical => sprite,
+    }
+  }
+}
+
+/// Draws a sprite from the tile sheet, dimmed if it is only remembered rather than in sight.
+/// The sprite's dark pixels show `bg`, so a see-through background lets whatever was drawn
+/// beneath it show around its edges.
+pub fn draw_sprite(ctx: &mut Rltk, x: i32, y: i32, sprite: u16, visible: bool, bg: RGBA) {
+  let tint = if visible {
+    RGB::named(rltk::WHITE)
+  } else {
+    RGB::from_f32(0.5, 0.5, 0.5)
+  };
+  layers::draw_on(ctx, layers::SPRITES, |ctx| ctx.set(x, y, tint, bg, sprite));
+}
@@ -0,0 +1,497 @@
+use super::{
+  try_move_player, Door, GameEvent, GameEvents, GameLog, LogCategory, LogLine, Map, Moonshot, Name,
+  Player, Position, RunState, TileType, Wildlife,
+};
+use crate::water_system::nearest_shore;
+use rltk::{BaseMap, DijkstraMap, Point, SmallVec, RGB};
+use specs::prelude::*;
+
+/// A walk the player has set off on, taken one step per turn.
+#[derive(Default)]
+pub struct Travel {
+  pub path: Vec<Point>,
+  /// Keep heading for the nearest unexplored tile instead of following a path.
+  pub exploring: bool,
+  /// Keep moving in this direction until something interesting happens.
+  pub running: Option<Point>,
+  /// How many open tiles surrounded the last step of a run.
+  pub openness: Option<usize>,
+  /// Turns of rest still to go, and how many have been taken so far.
+  pub resting: i32,
+  pub rested: i32,
+  pub seen: Vec<Entity>,
+  pub warning_mark: usize,
+  /// A sound from somewhere out of sight, heard while resting.
+  pub disturbance: Option<String>,
+}
+
+impl Travel {
+  fn stop(&mut self) {
+    self.path.clear();
+    self.exploring = false;
+    self.running = None;
+    self.resting = 0;
+  }
+}
+
+/// Named things on visible tiles, apart from the player.
+pub fn visible_entities(ecs: &World) -> Vec<Entity> {
+  let map = ecs.fetch::<Map>();
+  let entities = ecs.entities();
+  let names = ecs.read_storage::<Name>();
+  let positions = ecs.read_storage::<Position>();
+  let players = ecs.read_storage::<Player>();
+
+  (&entities, &names, &positions, !&players)
+    .join()
+    .filter(|(_, _, pos, _)| map.visible_tiles[map.xy_idx(pos.x, pos.y)])
+    .map(|(entity, _, _, _)| entity)
+    .collect()
+}
+
+/// The map as travel walks it, never wading out into deep water. Someone already swimming can
+/// still swim on, as `travel_step` lets them.
+struct DryMap<'a>(&'a Map);
+
+impl BaseMap for DryMap<'_> {
+  fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+    self.0.get_pathing_distance(idx1, idx2)
+  }
+
+  fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+    let mut exits = self.0.get_available_exits(idx);
+    if self.0.tiles[idx] != TileType::DeepWater {
+      exits.retain(|(exit, _)| self.0.tiles[*exit] != TileType::DeepWater);
+    }
+    exits
+  }
+}
+
+/// The map as the player knows it, so paths only cross tiles that have been revealed.
+struct KnownMap<'a>(&'a Map);
+
+impl BaseMap for KnownMap<'_> {
+  fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+    self.0.get_pathing_distance(idx1, idx2)
+  }
+
+  fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+    let mut exits = DryMap(self.0).get_available_exits(idx);
+    exits.retain(|(exit, _)| self.0.revealed_tiles[*exit]);
+    exits
+  }
+}
+
+/// The path last planned for the hover preview, and the target, player position and turn it
+/// was planned for.
+#[derive(Default)]
+pub struct PathPreview {
+  planned_for: Option<(Point, Point, i32)>,
+  path: Vec<Point>,
+}
+
+/// The path a click on `target` would follow. It is only planned again once the target, the
+/// player or the turn changes, rather than every frame.
+pub fn preview_path(ecs: &World, target: Point) -> Vec<Point> {
+  let key = (target, *ecs.fetch::<Point>(), ecs.fetch::<GameLog>().turn);
+  let mut preview = ecs.write_resource::<PathPreview>();
+  if preview.planned_for != Some(key) {
+    preview.path = plan_path(ecs, target).unwrap_or_default();
+    preview.planned_for = Some(key);
+  }
+  preview.path.clone()
+}
+
+/// The steps from the player to `target`, if it is a revealed walkable tile out of the deep water
+/// and reachable over revealed ground.
+pub fn plan_path(ecs: &World, target: Point) -> Option<Vec<Point>> {
+  let map = ecs.fetch::<Map>();
+  let player_pos = ecs.fetch::<Point>();
+
+  if target.x < 0 || target.x >= map.width || target.y < 0 || target.y >= map.height {
+    return None;
+  }
+  let target_idx = map.xy_idx(target.x, target.y);
+  if target == *player_pos
+    || !map.revealed_tiles[target_idx]
+    || map.blocked[target_idx]
+    || map.tiles[target_idx] == TileType::DeepWater
+  {
+    return None;
+  }
+
+  let path = rltk::a_star_search(
+    map.xy_idx(player_pos.x, player_pos.y),
+    target_idx,
+    &KnownMap(&map),
+  );
+  if !path.success {
+    return None;
+  }
+
+  Some(
+    path
+      .steps
+      .iter()
+      .skip(1)
+      .map(|idx| Point::new(*idx as i32 % map.width, *idx as i32 / map.width))
+      .collect(),
+  )
+}
+
+/// Sets off towards `target`. Returns false if there is no way there.
+pub fn start_travel(ecs: &mut World, target: Point) -> bool {
+  let path = match plan_path(ecs, target) {
+    Some(path) => path,
+    None => return false,
+  };
+  begin(ecs).path = path;
+  true
+}
+
+/// Sets off exploring, one step at a time towards whatever is nearest and still unrevealed.
+pub fn start_explore(ecs: &mut World) {
+  begin(ecs).exploring = true;
+}
+
+/// Starts running in a direction, taking the first step straight away. There is no running
+/// while a creature is in view, as the run would only stop for it once it moved.
+pub fn start_run(ecs: &mut World, delta_x: i32, delta_y: i32) -> RunState {
+  let creature = {
+    let wildlife = ecs.read_storage::<Wildlife>();
+    visible_entities(ecs)
+      .into_iter()
+      .find(|e| wildlife.get(*e).is_some())
+  };
+  if let Some(creature) = creature {
+    let names = ecs.read_storage::<Name>();
+    let name = names.get(creature).map_or("Something", |n| &n.name);
+    ecs.write_resource::<GameLog>().log(
+      LogLine::new(LogCategory::System)
+        .text("You can't run: ")
+        .coloured(name, RGB::named(rltk::MAGENTA))
+        .text(" is in view."),
+    );
+    return RunState::AwaitingInput;
+  }
+
+  begin(ecs).running = Some(Point::new(delta_x, delta_y));
+  travel_step(ecs)
+}
+
+/// Rests for up to `turns` turns, taking the first straight away.
+pub fn start_rest(ecs: &mut World, turns: i32) -> RunState {
+  let mut travel = begin(ecs);
+  travel.resting = turns;
+  travel.rested = 0;
+  drop(travel);
+  travel_step(ecs)
+}
+
+fn begin(ecs: &mut World) -> specs::shred::FetchMut<'_, Travel> {
+  let seen = visible_entities(ecs);
+  let warning_mark = ecs.fetch::<GameLog>().warnings;
+  let mut travel = ecs.write_resource::<Travel>();
+  travel.stop();
+  travel.openness = None;
+  travel.seen = seen;
+  travel.warning_mark = warning_mark;
+  travel.disturbance = None;
+  travel
+}
+
+pub fn is_travelling(ecs: &World) -> bool {
+  let travel = ecs.fetch::<Travel>();
+  travel.exploring || travel.running.is_some() || travel.resting > 0 || !travel.path.is_empty()
+}
+
+fn rested_for(turns: i32) -> String {
+  let plural = if turns == 1 { "" } else { "s" };
+  format!("You rested for {} turn{}", turns, plural)
+}
+
+/// Passes one turn of a rest, or ends it early if someone turned up or something was heard.
+fn rest_step(ecs: &mut World, newcomer: Option<Entity>) -> RunState {
+  let mut travel = ecs.write_resource::<Travel>();
+  let mut gamelog = ecs.write_resource::<GameLog>();
+  let names = ecs.read_storage::<Name>();
+
+  // Say how long the rest lasted, and what cut it short
+  let opening = if travel.rested > 0 {
+    format!("{} before ", rested_for(travel.rested))
+  } else {
+    "You stop resting: ".to_string()
+  };
+  let line = LogLine::new(LogCategory::System).text(opening);
+  let interrupted = if let Some(newcomer) = newcomer {
+    let name = names.get(newcomer).map_or("Something", |n| &n.name);
+    Some(
+      line
+        .coloured(name, RGB::named(rltk::MAGENTA))
+        .text(" came into view."),
+    )
+  } else {
+    travel
+      .disturbance
+      .take()
+      .map(|sound| line.text(format!("you heard {}.", sound)))
+  };
+
+  if let Some(interrupted) = interrupted {
+    gamelog.log(interrupted);
+    travel.stop();
+    return RunState::AwaitingInput;
+  }
+
+  travel.resting -= 1;
+  travel.rested += 1;
+  if travel.resting == 0 {
+    gamelog.log(LogLine::new(LogCategory::System).text(format!("{}.", rested_for(travel.rested))));
+  }
+  RunState::PlayerTurn
+}
+
+/// Notes sounds the player hears from out of sight while resting, so the rest can stop for them.
+pub struct DisturbanceSystem {}
+
+impl<'a> System<'a> for DisturbanceSystem {
+  type SystemData = (
+    ReadExpect<'a, GameEvents>,
+    ReadExpect<'a, Entity>,
+    ReadExpect<'a, Map>,
+    WriteExpect<'a, Travel>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (events, player_entity, map, mut travel) = data;
+
+    if travel.resting == 0 {
+      return;
+    }
+    for event in events.events.iter() {
+      if let GameEvent::HeardNoise {
+        listener,
+        source,
+        description,
+      } = event
+      {
+        if *listener == *player_entity && !map.visible_tiles[map.xy_idx(source.x, source.y)] {
+          travel.disturbance = Some(description.clone());
+        }
+      }
+    }
+  }
+}
+
+pub fn stop_travel(ecs: &mut World) {
+  ecs.write_resource::<Travel>().stop();
+}
+
+/// Counts the walkable tiles around a point.
+fn open_neighbours(map: &Map, at: Point) -> usize {
+  let mut open = 0;
+  for dy in -1..=1 {
+    for dx in -1..=1 {
+      let (x, y) = (at.x + dx, at.y + dy);
+      if (dx != 0 || dy != 0)
+        && x >= 0
+        && x < map.width
+        && y >= 0
+        && y < map.height
+        && !map.tiles[map.xy_idx(x, y)].is_blocked()
+      {
+        open += 1;
+      }
+    }
+  }
+  open
+}
+
+/// The next tile of a run, or None if it should end here: at a wall, in front of a door or deep
+/// water, or where a corridor opens up or branches off.
+fn run_target(
+  map: &Map,
+  from: Point,
+  direction: Point,
+  doors: &[usize],
+  openness: &mut Option<usize>,
+) -> Option<Point> {
+  let to = Point::new(from.x + direction.x, from.y + direction.y);
+  if to.x < 0 || to.x >= map.width || to.y < 0 || to.y >= map.height {
+    return None;
+  }
+  let idx = map.xy_idx(to.x, to.y);
+  let tile = map.tiles[idx];
+  if tile.is_blocked()
+    || tile == TileType::Door
+    || tile == TileType::DeepWater
+    || doors.contains(&idx)
+  {
+    return None;
+  }
+
+  let open = open_neighbours(map, from);
+  let previous = openness.replace(open);
+  if previous.is_some_and(|p| p <= 2 && p != open) {
+    return None;
+  }
+  Some(to)
+}
+
+/// The first step towards the nearest walkable tile that has not been revealed yet. Closed
+/// doors count as unexplored too, and are bumped open once reached.
+fn explore_target(map: &Map, from: Point, closed_doors: &[usize]) -> Option<Point> {
+  let unexplored: Vec<usize> = (0..map.tiles.len())
+    .filter(|idx| {
+      let tile = map.tiles[*idx];
+      !map.revealed_tiles[*idx] && !tile.is_blocked() && tile != TileType::DeepWater
+    })
+    .chain(closed_doors.iter().copied())
+    .collect();
+  if unexplored.is_empty() {
+    return None;
+  }
+
+  let to_point = |idx: usize| Point::new(idx as i32 % map.width, idx as i32 / map.width);
+  if let Some(door) = closed_doors
+    .iter()
+    .find(|idx| rltk::DistanceAlg::Chebyshev.distance2d(from, to_point(**idx)) < 1.5)
+  {
+    return Some(to_point(*door));
+  }
+
+  // The way on is all over dry land, so get out of the deep water first
+  let here = map.xy_idx(from.x, from.y);
+  if map.tiles[here] == TileType::DeepWater {
+    let shore = nearest_shore(map, from);
+    let path = rltk::a_star_search(here, map.xy_idx(shore.x, shore.y), &DryMap(map));
+    return path
+      .steps
+      .get(1)
+      .copied()
+      .filter(|_| path.success)
+      .map(to_point);
+  }
+
+  let distances = DijkstraMap::new(
+    map.width as usize,
+    map.height as usize,
+    &unexplored,
+    &DryMap(map),
+    (map.width * map.height) as f32,
+  );
+  if distances.map[here] == f32::MAX {
+    return None;
+  }
+  DijkstraMap::find_lowest_exit(&distances, here, &DryMap(map)).map(to_point)
+}
+
+/// Takes the next step of the walk, or stops it if something new came into view, a warning was
+/// logged, or the way is blocked.
+pub fn travel_step(ecs: &mut World) -> RunState {
+  let in_view = visible_entities(ecs);
+  let newcomer = {
+    let mut travel = ecs.write_resource::<Travel>();
+    let newcomer = in_view.iter().find(|e| !travel.seen.contains(e)).copied();
+    travel.seen = in_view;
+    newcomer
+  };
+  if ecs.fetch::<Travel>().resting > 0 {
+    return rest_step(ecs, newcomer);
+  }
+
+  let next = {
+    let mut travel = ecs.write_resource::<Travel>();
+
+    let mut gamelog = ecs.write_resource::<GameLog>();
+    let names = ecs.read_storage::<Name>();
+    let stop = LogLine::new(LogCategory::System).text("You stop walking: ");
+    if let Some(newcomer) = newcomer {
+      let name = names.get(newcomer).map_or("Something", |n| &n.name);
+      gamelog.log(
+        stop
+          .coloured(name, RGB::named(rltk::MAGENTA))
+          .text(" comes into view."),
+      );
+      travel.stop();
+      return RunState::AwaitingInput;
+    }
+
+    // Warnings are worth stopping for, but not Moonshot's antics or the weather
+    if gamelog.warnings != travel.warning_mark {
+      travel.stop();
+      return RunState::AwaitingInput;
+    }
+
+    let map = ecs.fetch::<Map>();
+    let player_pos = *ecs.fetch::<Point>();
+    let doors = ecs.read_storage::<Door>();
+    let positions = ecs.read_storage::<Position>();
+    let door_tiles: Vec<usize> = (&doors, &positions)
+      .join()
+      .map(|(_, pos)| map.xy_idx(pos.x, pos.y))
+      .collect();
+    let closed_doors: Vec<usize> = (&doors, &positions)
+      .join()
+      .filter(|(door, pos)| !door.open && map.revealed_tiles[map.xy_idx(pos.x, pos.y)])
+      .map(|(_, pos)| map.xy_idx(pos.x, pos.y))
+      .collect();
+    let next = if let Some(direction) = travel.running {
+      run_target(
+        &map,
+        player_pos,
+        direction,
+        &door_tiles,
+        &mut travel.openness,
+      )
+    } else if travel.exploring {
+      explore_target(&map, player_pos, &closed_doors)
+    } else {
+      travel.path.first().copied()
+    };
+    let next = match next {
+      Some(next) => next,
+      None => {
+        if travel.exploring {
+          gamelog.log(
+            LogLine::new(LogCategory::System).text("There is nothing left to explore from here."),
+          );
+        }
+        travel.stop();
+        return RunState::AwaitingInput;
+      }
+    };
+
+    let idx = map.xy_idx(next.x, next.y);
+    let here = map.tiles[map.xy_idx(player_pos.x, player_pos.y)];
+    if map.tiles[idx] == TileType::DeepWater && here != TileType::DeepWater {
+      gamelog.log(stop.text("deep water lies ahead."));
+      travel.stop();
+      return RunState::AwaitingInput;
+    }
+    if map.blocked[idx] && !closed_doors.contains(&idx) {
+      let moonshots = ecs.read_storage::<Moonshot>();
+      let blocker = map.tile_content[idx]
+        .iter()
+        .find(|e| moonshots.get(**e).is_some())
+        .and_then(|e| names.get(*e));
+      gamelog.log(match blocker {
+        Some(name) => stop
+          .coloured(&name.name, RGB::named(rltk::MAGENTA))
+          .text(" is in the way."),
+        None => stop.text("the way is blocked."),
+      });
+      travel.stop();
+      return RunState::AwaitingInput;
+    }
+
+    if !travel.path.is_empty() {
+      travel.path.remove(0);
+    }
+    next
+  };
+
+  let player_pos = *ecs.fetch::<Point>();
+  try_move_player(next.x - player_pos.x, next.y - player_pos.y, ecs);
+  RunState::PlayerTurn
+}
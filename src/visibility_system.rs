@@ -1,4 +1,4 @@
-use super::{BlocksVisibility, Map, Player, Position, Viewshed};
+use super::{BlocksVisibility, Map, Player, Position, Viewshed, Weather};
 use rltk::{field_of_view, Point};
 use specs::prelude::*;
 
@@ -7,6 +7,7 @@ pub struct VisibilitySystem {}
 impl<'a> System<'a> for VisibilitySystem {
   type SystemData = (
     WriteExpect<'a, Map>,
+    ReadExpect<'a, Weather>,
     Entities<'a>,
     WriteStorage<'a, Viewshed>,
     WriteStorage<'a, Position>,
@@ -15,7 +16,7 @@ impl<'a> System<'a> for VisibilitySystem {
   );
 
   fn run(&mut self, data: Self::SystemData) {
-    let (mut map, entities, mut viewshed, pos, player, blocks_visibility) = data;
+    let (mut map, weather, entities, mut viewshed, pos, player, blocks_visibility) = data;
 
     map.view_blocked.clear();
     for (block_pos, _block) in (&pos, &blocks_visibility).join() {
@@ -27,7 +28,8 @@ impl<'a> System<'a> for VisibilitySystem {
       if viewshed.dirty {
         viewshed.dirty = false;
         viewshed.visible_tiles.clear();
-        viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+        let range = weather.sight_range(viewshed.range, map.is_indoors(pos.x, pos.y));
+        viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), range, &*map);
         viewshed
           .visible_tiles
           .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);
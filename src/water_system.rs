@@ -0,0 +1,149 @@
+use super::{
+  GameEvent, GameEvents, InBackpack, Map, MoveDelay, Position, RunState, Stamina, TileType, Wet,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Turns something stays wet once it is out of the water.
+const WET_AFTER_WADING: i32 = 10;
+const WET_AFTER_SWIMMING: i32 = 40;
+
+/// The closest tile to `at` that can be stood on without swimming.
+pub fn nearest_shore(map: &Map, at: Point) -> Point {
+  let mut best: Option<(f32, Point)> = None;
+  for y in 0..map.height {
+    for x in 0..map.width {
+      let tile = map.tiles[map.xy_idx(x, y)];
+      if tile.is_blocked() || tile == TileType::DeepWater {
+        continue;
+      }
+      let here = Point::new(x, y);
+      let distance = rltk::DistanceAlg::PythagorasSquared.distance2d(at, here);
+      if best.is_none_or(|(d, _)| distance < d) {
+        best = Some((distance, here));
+      }
+    }
+  }
+  best.map_or(at, |(_, shore)| shore)
+}
+
+/// Slows down waders, tires out swimmers, makes them drop what they carry, and leaves everyone
+/// who went in wet for a while afterwards.
+pub struct WaterSystem {}
+
+impl<'a> System<'a> for WaterSystem {
+  type SystemData = (
+    ReadExpect<'a, Map>,
+    ReadExpect<'a, RunState>,
+    WriteExpect<'a, GameEvents>,
+    Entities<'a>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, InBackpack>,
+    WriteStorage<'a, Stamina>,
+    WriteStorage<'a, Wet>,
+    WriteStorage<'a, MoveDelay>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (
+      map,
+      runstate,
+      mut events,
+      entities,
+      mut positions,
+      mut backpack,
+      mut stamina,
+      mut wet,
+      mut delays,
+    ) = data;
+
+    let moves: Vec<(Entity, Point)> = events
+      .events
+      .iter()
+      .filter_map(|event| match event {
+        GameEvent::Moved { entity, to } => Some((*entity, *to)),
+        _ => None,
+      })
+      .collect();
+    for (entity, to) in moves {
+      let soaked = match map.tiles[map.xy_idx(to.x, to.y)] {
+        TileType::ShallowWater => {
+          delays
+            .insert(entity, MoveDelay { turns: 1 })
+            .expect("Unable to insert move delay");
+          WET_AFTER_WADING
+        }
+        TileType::DeepWater => {
+          // Swimming on an empty tank is slow going too
+          if stamina.get(entity).is_some_and(|s| s.current == 0) {
+            delays
+              .insert(entity, MoveDelay { turns: 1 })
+              .expect("Unable to insert move delay");
+          }
+          WET_AFTER_SWIMMING
+        }
+        _ => continue,
+      };
+      let turns = wet.get(entity).map_or(0, |w| w.turns).max(soaked);
+      wet
+        .insert(entity, Wet { turns })
+        .expect("Unable to insert wet");
+    }
+
+    // Everything else happens once a turn
+    if *runstate != RunState::NpcTurn {
+      return;
+    }
+
+    for (pos, stamina) in (&positions, &mut stamina).join() {
+      if map.tiles[map.xy_idx(pos.x, pos.y)] == TileType::DeepWater {
+        stamina.current = (stamina.current - 1).max(0);
+      } else {
+        stamina.current = (stamina.current + 1).min(stamina.max);
+      }
+    }
+
+    let mut dried: Vec<Entity> = Vec::new();
+    for (entity, pos, wet) in (&entities, &positions, &mut wet).join() {
+      if !map.tiles[map.xy_idx(pos.x, pos.y)].is_water() {
+        wet.turns -= 1;
+        if wet.turns <= 0 {
+          dried.push(entity);
+        }
+      }
+    }
+    for entity in dried.iter() {
+      wet.remove(*entity);
+    }
+
+    // Nobody can keep hold of anything while swimming, so it floats off to the shore
+    let swept: Vec<(Entity, Entity, Point)> = (&entities, &backpack)
+      .join()
+      .filter_map(|(item, carried)| {
+        let pos = positions.get(carried.owner)?;
+        (map.tiles[map.xy_idx(pos.x, pos.y)] == TileType::DeepWater).then_some((
+          item,
+          carried.owner,
+          Point::new(pos.x, pos.y),
+        ))
+      })
+      .collect();
+    for (item, owner, at) in swept {
+      let shore = nearest_shore(&map, at);
+      backpack.remove(item);
+      positions
+        .insert(
+          item,
+          Position {
+            x: shore.x,
+            y: shore.y,
+          },
+        )
+        .expect("Unable to insert position");
+      events.emit(GameEvent::LostInWater {
+        entity: owner,
+        item,
+      });
+    }
+  }
+}
@@ -0,0 +1,231 @@
+use super::{
+  camera, layers, map::tile_variation, GameEvent, GameEvents, Map, RunState, Settings,
+  TerrainAnimation, Viewshed, VIEW_HEIGHT, VIEW_WIDTH,
+};
+use rltk::{Point, RandomNumberGenerator, Rltk, RGB};
+use specs::prelude::*;
+
+/// How far anyone can see out in the fog.
+const FOG_SIGHT: i32 = 4;
+/// Loudness lost to the patter of rain.
+const RAIN_MUFFLE: i32 = 3;
+/// How much scent the rain washes away each turn, against 1 on a dry day.
+const RAIN_SCENT_FADE: i32 = 4;
+/// Loudness a sound gains downwind of where it was made, and loses upwind.
+const WIND_CARRY: i32 = 3;
+/// How long a spell of weather lasts, in turns.
+const MIN_SPELL: i32 = 60;
+const MAX_SPELL: i32 = 180;
+
+/// Milliseconds between each step of the rain and wind overlays.
+const OVERLAY_STEP_MS: f32 = 120.0;
+
+/// The directions the wind can blow in, with where it blows from in short and in full.
+const WINDS: [(i32, i32, &str, &str); 8] = [
+  (1, 0, "W", "west"),
+  (1, 1, "NW", "northwest"),
+  (0, 1, "N", "north"),
+  (-1, 1, "NE", "northeast"),
+  (-1, 0, "E", "east"),
+  (-1, -1, "SE", "southeast"),
+  (0, -1, "S", "south"),
+  (1, -1, "SW", "southwest"),
+];
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum WeatherKind {
+  Clear,
+  Rain,
+  Fog,
+  Wind,
+}
+
+/// The weather out on the map. Sight and scent inside the hut are sheltered from it, but sounds
+/// are not.
+#[derive(Copy, Clone, Debug)]
+pub struct Weather {
+  pub kind: WeatherKind,
+  /// Which of the `WINDS` is blowing, when it is windy.
+  wind: usize,
+  /// Turns until the weather may change.
+  pub turns_left: i32,
+}
+
+impl Default for Weather {
+  fn default() -> Weather {
+    Weather {
+      kind: WeatherKind::Clear,
+      wind: 0,
+      turns_left: MAX_SPELL,
+    }
+  }
+}
+
+impl Weather {
+  /// A short description for the side panel.
+  pub fn describe(&self) -> String {
+    match self.kind {
+      WeatherKind::Clear => "Clear".to_string(),
+      WeatherKind::Rain => "Rain".to_string(),
+      WeatherKind::Fog => "Fog".to_string(),
+      WeatherKind::Wind => format!("Wind from {}", WINDS[self.wind].2),
+    }
+  }
+
+  /// What the log says when this weather sets in.
+  pub fn announcement(&self) -> String {
+    match self.kind {
+      WeatherKind::Clear => "The weather clears.".to_string(),
+      WeatherKind::Rain => "It starts to rain.".to_string(),
+      WeatherKind::Fog => "A thick fog rolls in.".to_string(),
+      WeatherKind::Wind => format!("A wind picks up from the {}.", WINDS[self.wind].3),
+    }
+  }
+
+  /// The step the wind moves things by, if it is blowing.
+  pub fn wind(&self) -> Option<Point> {
+    match self.kind {
+      WeatherKind::Wind => {
+        let (x, y, _, _) = WINDS[self.wind];
+        Some(Point::new(x, y))
+      }
+      _ => None,
+    }
+  }
+
+  /// How far something can see from where it stands.
+  pub fn sight_range(&self, range: i32, indoors: bool) -> i32 {
+    match self.kind {
+      WeatherKind::Fog if !indoors => range.min(FOG_SIGHT),
+      _ => range,
+    }
+  }
+
+  /// How much louder or quieter a sound made at `source` is by the time it reaches `listener`.
+  pub fn hearing(&self, source: Point, listener: Point) -> i32 {
+    match self.kind {
+      WeatherKind::Rain => -RAIN_MUFFLE,
+      WeatherKind::Wind => {
+        let (x, y, _, _) = WINDS[self.wind];
+        let downwind = (listener.x - source.x) * x + (listener.y - source.y) * y;
+        downwind.signum() * WIND_CARRY
+      }
+      _ => 0,
+    }
+  }
+
+  /// How much scent an outdoor tile loses each turn.
+  pub fn scent_fade(&self) -> i32 {
+    match self.kind {
+      WeatherKind::Rain => RAIN_SCENT_FADE,
+      _ => 1,
+    }
+  }
+
+  /// Picks the weather for the next spell. It may well stay the same.
+  fn roll(rng: &mut RandomNumberGenerator) -> Weather {
+    let kind = match rng.roll_dice(1, 10) {
+      1..=4 => WeatherKind::Clear,
+      5..=6 => WeatherKind::Rain,
+      7..=8 => WeatherKind::Fog,
+      _ => WeatherKind::Wind,
+    };
+    Weather {
+      kind,
+      wind: rng.roll_dice(1, WINDS.len() as i32) as usize - 1,
+      turns_left: rng.range(MIN_SPELL, MAX_SPELL + 1),
+    }
+  }
+
+  fn same_as(&self, other: &Weather) -> bool {
+    self.kind == other.kind && (self.kind != WeatherKind::Wind || self.wind == other.wind)
+  }
+}
+
+/// Changes the weather every so often, and lets everyone see how far they can now see.
+pub struct WeatherSystem {}
+
+impl<'a> System<'a> for WeatherSystem {
+  type SystemData = (
+    ReadExpect<'a, RunState>,
+    WriteExpect<'a, Weather>,
+    WriteExpect<'a, RandomNumberGenerator>,
+    WriteExpect<'a, GameEvents>,
+    WriteStorage<'a, Viewshed>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (runstate, mut weather, mut rng, mut events, mut viewsheds) = data;
+
+    // The weather only moves on once a turn
+    if *runstate != RunState::NpcTurn {
+      return;
+    }
+    weather.turns_left -= 1;
+    if weather.turns_left > 0 {
+      return;
+    }
+
+    let next = Weather::roll(&mut rng);
+    let changed = !next.same_as(&weather);
+    *weather = next;
+    if changed {
+      events.emit(GameEvent::WeatherChanged { weather: next });
+      for viewshed in (&mut viewsheds).join() {
+        viewshed.dirty = true;
+      }
+    }
+  }
+}
+
+/// Draws the weather over the outdoor tiles in view: falling rain, drifting fog or streaks of
+/// wind. The rain and wind hold still if the player has asked for less motion.
+pub fn draw_weather(ecs: &World, ctx: &mut Rltk) {
+  let weather = ecs.fetch::<Weather>();
+  if weather.kind == WeatherKind::Clear {
+    return;
+  }
+  let map = ecs.fetch::<Map>();
+  let player_pos = *ecs.fetch::<Point>();
+  let origin = camera::origin(ecs);
+  let step = if ecs.fetch::<Settings>().reduce_motion {
+    0
+  } else {
+    (ecs.fetch::<TerrainAnimation>().elapsed_ms / OVERLAY_STEP_MS) as i32
+  };
+  let wind = weather.wind().unwrap_or_else(|| Point::new(0, 1));
+  let streak = match (wind.x, wind.y) {
+    (0, _) => '|',
+    (_, 0) => '-',
+    (x, y) if x == y => '\\',
+    _ => '/',
+  };
+
+  for y in 0..VIEW_HEIGHT.min(map.height) {
+    for x in 0..VIEW_WIDTH.min(map.width) {
+      let (tx, ty) = (origin.x + x, origin.y + y);
+      let idx = map.xy_idx(tx, ty);
+      if !map.visible_tiles[idx] || map.is_indoors(tx, ty) {
+        continue;
+      }
+      if weather.kind == WeatherKind::Fog {
+        // Thicker the further away it is
+        let distance = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, Point::new(tx, ty));
+        let thickness = (0.15 + 0.5 * distance / FOG_SIGHT as f32).min(0.65);
+        ctx.set_bg(x, y, RGB::named(rltk::LIGHT_GRAY).to_rgba(thickness));
+        continue;
+      }
+
+      // Follow each drop or gust back along its path to where it started, so it moves with time
+      let (sx, sy) = (tx - wind.x * step, ty - wind.y * step);
+      let seed = tile_variation((sy.rem_euclid(4096) * 4096 + sx.rem_euclid(4096)) as usize);
+      let (every, colour) = match weather.kind {
+        WeatherKind::Rain => (12, RGB::from_f32(0.4, 0.5, 0.9)),
+        _ => (40, RGB::named(rltk::LIGHT_GRAY)),
+      };
+      if seed.is_multiple_of(every) && map.tile_content[idx].is_empty() {
+        ctx.set(x, y, colour, layers::clear(), rltk::to_cp437(streak));
+      }
+    }
+  }
+}
@@ -0,0 +1,90 @@
+use super::{Map, Moonshot, Player, Position, RunState, TileType, Viewshed, Wildlife};
+use rltk::{BaseMap, DijkstraMap, Point};
+use specs::prelude::*;
+
+pub struct WildlifeAI {}
+
+impl<'a> System<'a> for WildlifeAI {
+  type SystemData = (
+    WriteExpect<'a, Map>,
+    ReadExpect<'a, RunState>,
+    WriteExpect<'a, rltk::RandomNumberGenerator>,
+    WriteStorage<'a, Viewshed>,
+    WriteStorage<'a, Position>,
+    ReadStorage<'a, Wildlife>,
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, Moonshot>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (mut map, runstate, mut rng, mut viewsheds, mut positions, wildlife, players, moonshots) =
+      data;
+
+    if *runstate != RunState::NpcTurn {
+      return;
+    }
+
+    // Both the player and the dog are scary
+    let threats: Vec<Point> = (&positions, &players)
+      .join()
+      .map(|(p, _)| Point::new(p.x, p.y))
+      .chain(
+        (&positions, &moonshots)
+          .join()
+          .map(|(p, _)| Point::new(p.x, p.y)),
+      )
+      .collect();
+
+    for (viewshed, pos, animal) in (&mut viewsheds, &mut positions, &wildlife).join() {
+      let here = Point::new(pos.x, pos.y);
+      let scared_of: Vec<usize> = threats
+        .iter()
+        .filter(|t| {
+          viewshed.visible_tiles.contains(t)
+            && rltk::DistanceAlg::Pythagoras.distance2d(here, **t) < animal.fear_range as f32
+        })
+        .map(|t| map.xy_idx(t.x, t.y))
+        .collect();
+
+      let idx = map.xy_idx(pos.x, pos.y);
+      let destination = if !scared_of.is_empty() {
+        let flee_map = DijkstraMap::new(
+          map.width as usize,
+          map.height as usize,
+          &scared_of,
+          &*map,
+          animal.fear_range as f32 * 2.0,
+        );
+        // The furthest exit from danger that isn't out into the deep water
+        map
+          .get_available_exits(idx)
+          .iter()
+          .map(|(exit, _)| *exit)
+          .filter(|exit| map.tiles[*exit] != TileType::DeepWater)
+          .max_by(|a, b| flee_map.map[*a].total_cmp(&flee_map.map[*b]))
+      } else if rng.roll_dice(1, 3) == 1 {
+        // Idle wandering
+        let x = pos.x + rng.range(-1, 2);
+        let y = pos.y + rng.range(-1, 2);
+        if x >= 0 && x < map.width && y >= 0 && y < map.height {
+          Some(map.xy_idx(x, y))
+        } else {
+          None
+        }
+      } else {
+        None
+      };
+
+      // Animals never wander out into the deep water either
+      if let Some(destination) = destination {
+        if !map.blocked[destination] && map.tiles[destination] != TileType::DeepWater {
+          map.blocked[idx] = false;
+          map.blocked[destination] = true;
+          pos.x = destination as i32 % map.width;
+          pos.y = destination as i32 / map.width;
+          viewshed.dirty = true;
+        }
+      }
+    }
+  }
+}